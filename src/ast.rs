@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+
+/// A shell operator between or around words.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShellOp {
+    /// `|`
+    Pipe,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+    /// `;`
+    Seq,
+    /// `&` at the end of a pipeline.
+    Background,
+    /// A redirection operator with its fd, e.g. `>`, `2>>`, `<`, `&>` or `2>&1`.
+    /// Duplications (`2>&1`, `>&-`) are complete; the others take the next word.
+    Redirect(String),
+    /// `(` opening a subshell.
+    SubshellOpen,
+    /// `)` closing a subshell.
+    SubshellClose,
+}
+
+impl ShellOp {
+    /// The operator as written in POSIX shells.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ShellOp::Pipe => "|",
+            ShellOp::And => "&&",
+            ShellOp::Or => "||",
+            ShellOp::Seq => ";",
+            ShellOp::Background => "&",
+            ShellOp::Redirect(op) => op,
+            ShellOp::SubshellOpen => "(",
+            ShellOp::SubshellClose => ")",
+        }
+    }
+
+    /// True for `&&`, `||`, `;` and `&`, which separate pipelines.
+    pub fn is_list_op(&self) -> bool {
+        matches!(self, ShellOp::And | ShellOp::Or | ShellOp::Seq | ShellOp::Background)
+    }
+
+    /// True for a redirection that needs a target word after it.
+    pub fn takes_target(&self) -> bool {
+        match self {
+            ShellOp::Redirect(op) => !op.contains(">&") && !op.contains("<&"),
+            _ => false,
+        }
+    }
+}
+
+/// One lexed unit of a command line: a word (quotes removed) or an operator.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Word(String),
+    Op(ShellOp),
+}
+
+impl Token {
+    pub fn as_word(&self) -> Option<&str> {
+        match self {
+            Token::Word(w) => Some(w),
+            Token::Op(_) => None,
+        }
+    }
+
+    /// Words as themselves, operators as their POSIX spelling.
+    pub fn into_text(self) -> String {
+        match self {
+            Token::Word(w) => w,
+            Token::Op(op) => op.as_str().to_string(),
+        }
+    }
+}
+
+/// Pipelines joined by list operators: `make && ./run || echo failed; ls`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandList {
+    pub items: Vec<ListItem>,
+}
+
+/// A pipeline and the list operator after it; only the last may have none.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListItem {
+    pub pipeline: Pipeline,
+    pub terminator: Option<ShellOp>,
+}
+
+/// Commands joined by `|`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    Subshell { body: CommandList, redirects: Vec<Redirect> },
+}
+
+/// Words of one command. Redirections are kept apart and written after the words.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimpleCommand {
+    pub words: Vec<String>,
+    pub redirects: Vec<Redirect>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redirect {
+    pub op: ShellOp,
+    pub target: Option<String>,
+}
+
+impl CommandList {
+    /// Build the tree from lexed tokens; `None` when the operators don't form a
+    /// command line (`| grep`, `a &&`, unbalanced parentheses, a dangling `>`).
+    pub fn parse(tokens: &[Token]) -> Option<Self> {
+        let mut pos = 0;
+        let list = parse_list(tokens, &mut pos)?;
+        (pos == tokens.len()).then_some(list)
+    }
+
+    /// Flatten back into tokens, in order.
+    pub fn to_tokens(&self) -> Vec<Token> {
+        let mut out = Vec::new();
+        self.push_tokens(&mut out);
+        out
+    }
+
+    fn push_tokens(&self, out: &mut Vec<Token>) {
+        for item in &self.items {
+            item.pipeline.push_tokens(out);
+            out.extend(item.terminator.iter().cloned().map(Token::Op));
+        }
+    }
+
+    /// Every simple command on its own, left to right, including those inside
+    /// subshells: the segments of `a | b && (c; d)` are `a`, `b`, `c` and `d`.
+    pub fn simple_commands(&self) -> Vec<&SimpleCommand> {
+        let mut out = Vec::new();
+        for item in &self.items {
+            for command in &item.pipeline.commands {
+                match command {
+                    Command::Simple(simple) => out.push(simple),
+                    Command::Subshell { body, .. } => out.extend(body.simple_commands()),
+                }
+            }
+        }
+        out
+    }
+
+    /// The trailing commands of every pipeline, longest first:
+    /// `ps aux | grep -v grep | wc -l` gives `grep -v grep | wc -l` and `wc -l`.
+    pub fn pipeline_tails(&self) -> Vec<Pipeline> {
+        let mut out = Vec::new();
+        for item in &self.items {
+            let commands = &item.pipeline.commands;
+            for start in 1..commands.len() {
+                out.push(Pipeline { commands: commands[start..].to_vec() });
+            }
+            for command in commands {
+                if let Command::Subshell { body, .. } = command {
+                    out.extend(body.pipeline_tails());
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Pipeline {
+    pub fn to_tokens(&self) -> Vec<Token> {
+        let mut out = Vec::new();
+        self.push_tokens(&mut out);
+        out
+    }
+
+    fn push_tokens(&self, out: &mut Vec<Token>) {
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                out.push(Token::Op(ShellOp::Pipe));
+            }
+            let redirects = match command {
+                Command::Simple(simple) => {
+                    out.extend(simple.words.iter().cloned().map(Token::Word));
+                    &simple.redirects
+                }
+                Command::Subshell { body, redirects } => {
+                    out.push(Token::Op(ShellOp::SubshellOpen));
+                    body.push_tokens(out);
+                    out.push(Token::Op(ShellOp::SubshellClose));
+                    redirects
+                }
+            };
+            push_redirects(redirects, out);
+        }
+    }
+}
+
+impl SimpleCommand {
+    pub fn to_tokens(&self) -> Vec<Token> {
+        let mut out: Vec<Token> = self.words.iter().cloned().map(Token::Word).collect();
+        push_redirects(&self.redirects, &mut out);
+        out
+    }
+}
+
+fn push_redirects(redirects: &[Redirect], out: &mut Vec<Token>) {
+    for r in redirects {
+        out.push(Token::Op(r.op.clone()));
+        out.extend(r.target.iter().cloned().map(Token::Word));
+    }
+}
+
+fn parse_list(tokens: &[Token], pos: &mut usize) -> Option<CommandList> {
+    let mut items = Vec::new();
+    loop {
+        let pipeline = parse_pipeline(tokens, pos)?;
+        let terminator = match tokens.get(*pos) {
+            Some(Token::Op(op)) if op.is_list_op() => {
+                *pos += 1;
+                Some(op.clone())
+            }
+            _ => None,
+        };
+        let at_end = matches!(tokens.get(*pos), None | Some(Token::Op(ShellOp::SubshellClose)));
+        let more = terminator.is_some() && !at_end;
+        // `a &&` / `a ||` must be followed by another pipeline.
+        if at_end && matches!(terminator, Some(ShellOp::And | ShellOp::Or)) {
+            return None;
+        }
+        items.push(ListItem { pipeline, terminator });
+        if !more {
+            return Some(CommandList { items });
+        }
+    }
+}
+
+fn parse_pipeline(tokens: &[Token], pos: &mut usize) -> Option<Pipeline> {
+    let mut commands = vec![parse_command(tokens, pos)?];
+    while let Some(Token::Op(ShellOp::Pipe)) = tokens.get(*pos) {
+        *pos += 1;
+        commands.push(parse_command(tokens, pos)?);
+    }
+    Some(Pipeline { commands })
+}
+
+fn parse_command(tokens: &[Token], pos: &mut usize) -> Option<Command> {
+    if let Some(Token::Op(ShellOp::SubshellOpen)) = tokens.get(*pos) {
+        *pos += 1;
+        let body = parse_list(tokens, pos)?;
+        if tokens.get(*pos) != Some(&Token::Op(ShellOp::SubshellClose)) {
+            return None;
+        }
+        *pos += 1;
+        let mut redirects = Vec::new();
+        while let Some(Token::Op(op @ ShellOp::Redirect(_))) = tokens.get(*pos) {
+            *pos += 1;
+            redirects.push(parse_redirect_target(op, tokens, pos)?);
+        }
+        return Some(Command::Subshell { body, redirects });
+    }
+
+    let mut simple = SimpleCommand::default();
+    loop {
+        match tokens.get(*pos) {
+            Some(Token::Word(w)) => {
+                *pos += 1;
+                simple.words.push(w.clone());
+            }
+            Some(Token::Op(op @ ShellOp::Redirect(_))) => {
+                *pos += 1;
+                simple.redirects.push(parse_redirect_target(op, tokens, pos)?);
+            }
+            _ => break,
+        }
+    }
+    (!simple.words.is_empty() || !simple.redirects.is_empty()).then_some(Command::Simple(simple))
+}
+
+fn parse_redirect_target(op: &ShellOp, tokens: &[Token], pos: &mut usize) -> Option<Redirect> {
+    let target = if op.takes_target() {
+        let Some(Token::Word(w)) = tokens.get(*pos) else {
+            return None;
+        };
+        *pos += 1;
+        Some(w.clone())
+    } else {
+        None
+    };
+    Some(Redirect { op: op.clone(), target })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::{ShellTokenizer, Tokenizer};
+
+    #[test]
+    fn parses_lists_pipelines_and_subshells() {
+        let tokens = ShellTokenizer.lex("(cd web && make) 2>&1 | tee log; ps aux | grep x > out &");
+        let list = CommandList::parse(&tokens).unwrap();
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.items[0].terminator, Some(ShellOp::Seq));
+        assert_eq!(list.items[1].terminator, Some(ShellOp::Background));
+        assert_eq!(list.items[0].pipeline.commands.len(), 2);
+        let Command::Subshell { body, redirects } = &list.items[0].pipeline.commands[0] else {
+            panic!("expected a subshell");
+        };
+        assert_eq!(body.items[0].terminator, Some(ShellOp::And));
+        assert_eq!(redirects[0].op, ShellOp::Redirect("2>&1".into()));
+        assert_eq!(list.to_tokens(), tokens);
+
+        let words: Vec<&str> = list.simple_commands().iter().map(|c| c.words[0].as_str()).collect();
+        assert_eq!(words, vec!["cd", "make", "tee", "ps", "grep"]);
+        let tails: Vec<usize> = list.pipeline_tails().iter().map(|p| p.commands.len()).collect();
+        assert_eq!(tails, vec![1, 1]);
+    }
+
+    #[test]
+    fn rejects_malformed_operator_use() {
+        for bad in ["| grep x", "make &&", "(cd x", "ls )", "echo >", "a | | b"] {
+            assert!(CommandList::parse(&ShellTokenizer.lex(bad)).is_none(), "{bad}");
+        }
+    }
+}
@@ -12,6 +12,7 @@ use std::path::PathBuf;
   alman add --command \"git status\" gs
   alman remove gs
   alman change old-alias new-alias
+  alman edit gs --command \"git status -sb\"
   alman list
   alman get-suggestions -n 10
   alman tui"
@@ -27,16 +28,69 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Operation {
-    /// Add a new alias
-    #[command(after_help = "EXAMPLE:
-  alman add --command \"git status\" gs")]
+    /// Add a new alias, or a function when the command has placeholders
+    #[command(after_help = "EXAMPLES:
+  alman add --command \"git status\" gs
+  alman add -c 'docker exec -it {1} bash' dex
+  alman add -c 'docker exec -it {container} {shell=bash}' dsh
+  alman add -c 'rg --hidden {...}' rgh
+
+PLACEHOLDERS:
+  {1} {2} ...          arguments by position
+  {name}               a named argument, numbered in order of appearance
+  {name=default}       an argument that may be left out ({1:name=default} when numbering)
+  {...}                every remaining argument (last word of its command)
+  Braces in quotes, after $ or @, empty ({}) or brace expansions ({a,b}) are kept as typed.")]
     Add {
-        /// Command to associate with the alias
-        #[arg(short = 'c', long, help = "Command to associate with the alias")]
+        /// Command to associate with the alias; may contain placeholders
+        #[arg(short = 'c', long, help = "Command to associate with the alias; {1}, {name} or {...} make it take arguments")]
         command: String,
         /// Alias name to add
         alias: String,
     },
+    /// Add or update a function with hand-written bodies for each shell
+    #[command(name = "add-raw", after_help = "EXAMPLES:
+  alman add-raw mkcd --posix 'mkdir -p \"$1\" && cd \"$1\"' --fish 'mkdir -p $argv[1]; and cd $argv[1]'
+  alman add-raw up --bash @up.bash
+
+Bash and zsh use the --posix body when they have none of their own. Bodies of an
+existing raw function are kept unless replaced; pass '' to drop one.")]
+    #[command(group(clap::ArgGroup::new("bodies").required(true).multiple(true)))]
+    AddRaw {
+        /// Function name
+        name: String,
+        /// Body for any POSIX shell; @FILE reads it from a file
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for any POSIX shell (@FILE reads a file)")]
+        posix: Option<String>,
+        /// Body for bash only
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for bash (@FILE reads a file)")]
+        bash: Option<String>,
+        /// Body for zsh only
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for zsh (@FILE reads a file)")]
+        zsh: Option<String>,
+        /// Body for fish
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for fish (@FILE reads a file)")]
+        fish: Option<String>,
+        /// Body for nushell; arguments are in `$args`
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for nushell, with arguments in $args (@FILE reads a file)")]
+        nu: Option<String>,
+        /// Body for PowerShell; arguments are in `$args`
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for PowerShell, with arguments in $args (@FILE reads a file)")]
+        pwsh: Option<String>,
+        /// Body for tcsh: an alias line, with arguments as `!:1`, `!*`
+        #[arg(long, group = "bodies", value_name = "BODY", help = "Body for tcsh, an alias with arguments as !:1 or !* (@FILE reads a file)")]
+        tcsh: Option<String>,
+    },
+    /// Manage exported environment variables
+    #[command(after_help = "EXAMPLES:
+  alman env set EDITOR nvim
+  alman env set KUBECONFIG '$HOME/.kube/config'
+  alman env unset EDITOR
+  alman env list")]
+    Env {
+        #[command(subcommand)]
+        action: EnvAction,
+    },
     /// Remove an existing alias
     #[command(after_help = "EXAMPLE:
   alman remove gs")]
@@ -57,13 +111,40 @@ pub enum Operation {
         /// New alias name
         new_alias: String,
     },
+    /// Edit the command of an existing alias or function
+    #[command(after_help = "EXAMPLES:
+  alman edit gs --command \"git status -sb\"
+  alman edit dex --command 'docker exec -it {container} sh'
+  alman edit dex --template '{\"parts\":[{\"Literal\":\"docker\"},{\"Literal\":\"exec\"},{\"Slot\":1},{\"Literal\":\"sh\"}]}'
+  alman edit gs                # opens $VISUAL / $EDITOR")]
+    Edit {
+        /// Alias or function name to edit
+        name: String,
+        /// New command; may contain placeholders as in `alman add`
+        #[arg(short = 'c', long, conflicts_with = "template", help = "New command; may contain placeholders as in `alman add`")]
+        command: Option<String>,
+        /// New body as a template in JSON
+        #[arg(long, help = "New body as a JSON template")]
+        template: Option<String>,
+        /// Also rename the definition
+        #[arg(long, value_name = "NEW_NAME", help = "Also rename the definition")]
+        rename: Option<String>,
+    },
     /// Get intelligent alias suggestions based on command history
-    #[command(after_help = "EXAMPLE:
-  alman get-suggestions -n 10")]
+    #[command(after_help = "EXAMPLES:
+  alman get-suggestions -n 10
+  alman get-suggestions --here
+  alman get-suggestions --dir ~/src/api")]
     GetSuggestions {
         /// Number of suggestions to display
         #[arg(short = 'n', long, help = "Number of suggestions to display")]
         num: Option<usize>,
+        /// Rank by usage in the current directory (or its git repository root)
+        #[arg(long, conflicts_with = "dir", help = "Rank by usage in the current directory or its git root")]
+        here: bool,
+        /// Rank by usage in the given directory (or its git repository root)
+        #[arg(long, value_name = "PATH", help = "Rank by usage in PATH or its git root")]
+        dir: Option<PathBuf>,
     },
     /// Delete alias suggestions for a specific alias
     #[command(after_help = "EXAMPLE:
@@ -72,19 +153,53 @@ pub enum Operation {
         /// Alias name to delete suggestions for
         alias: String,
     },
+    /// Show which saved aliases or functions produce a command
+    #[command(name = "which-def", after_help = "EXAMPLES:
+  alman which-def \"docker exec web bash\"
+  alman which-def --json git checkout -b main")]
+    WhichDef {
+        /// Print the matches as JSON
+        #[arg(long, help = "Print the matches as JSON")]
+        json: bool,
+        /// The full command line
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Record commands appended to a history file since the last sync
+    #[command(name = "sync-history", after_help = "EXAMPLES:
+  alman sync-history
+  alman sync-history --file ~/.sh_history
+
+For shells without command hooks (dash, ksh): each call reads only the new lines.
+The posix integration runs it at every prompt; with \"hooks\": false in the config
+it also runs when the TUI or get-suggestions starts.")]
+    SyncHistory {
+        /// History files to read; $HISTFILE or the detected one by default
+        #[arg(long = "file", value_name = "PATH", help = "History file to read (repeatable; default: $HISTFILE or the detected one)")]
+        files: Vec<PathBuf>,
+    },
     /// Launch the interactive terminal user interface (TUI)
     #[command(after_help = "EXAMPLE:
   alman tui")]
     Tui,
-    #[command(hide = true)]
+    #[command(hide = true, after_help = "BASH HOOKS:
+  auto           bash-preexec when it is loaded (atuin, starship, ble.sh use it), else trap
+  bash-preexec   register in preexec_functions / precmd_functions, even if bash-preexec loads later
+  trap           a DEBUG trap that keeps running any trap already installed")]
     Init {
-        #[arg(value_enum, help = "Shell type to initialize (bash, zsh, fish, posix)")]
+        #[arg(value_enum, help = "Shell type to initialize (bash, zsh, fish, posix, nu, pwsh, tcsh)")]
         shell: InitShell,
+        /// How the bash integration sees commands run
+        #[arg(long, value_enum, default_value_t = BashHook::Auto, help = "How bash commands are tracked: auto, bash-preexec or trap")]
+        hook: BashHook,
     },
     #[command(hide = true)]
     InitData,
     #[command(hide = true)]
     ListAliasFiles,
+    /// remind <command> — hidden, called from shell hooks before a command runs
+    #[command(hide = true)]
+    Remind { command: String },
     /// render-aliases <shell> — hidden, called from shell init
     #[command(name = "render-aliases", hide = true)]
     RenderAliases { shell: InitShell },
@@ -99,6 +214,41 @@ pub enum Operation {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum EnvAction {
+    /// Export a variable from every shell alman is set up in
+    Set {
+        /// Variable name
+        name: String,
+        /// Value; `$NAME`, `${NAME}` and a leading `~` are expanded by the shell
+        value: String,
+    },
+    /// Stop exporting a variable
+    Unset {
+        /// Variable name
+        name: String,
+    },
+    /// List exported variables
+    List,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BashHook {
+    Auto,
+    BashPreexec,
+    Trap,
+}
+
+impl BashHook {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BashHook::Auto => "auto",
+            BashHook::BashPreexec => "bash-preexec",
+            BashHook::Trap => "trap",
+        }
+    }
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum InitShell {
     Bash,
@@ -106,4 +256,25 @@ pub enum InitShell {
     Fish,
     #[clap(alias = "ksh")]
     Posix,
+    #[clap(alias = "nushell")]
+    Nu,
+    #[clap(alias = "powershell")]
+    Pwsh,
+    #[clap(alias = "csh")]
+    Tcsh,
+}
+
+impl InitShell {
+    /// The dialect of raw function bodies this shell runs.
+    pub fn dialect(&self) -> &'static str {
+        match self {
+            InitShell::Bash => "bash",
+            InitShell::Zsh => "zsh",
+            InitShell::Fish => "fish",
+            InitShell::Posix => "posix",
+            InitShell::Nu => "nu",
+            InitShell::Pwsh => "pwsh",
+            InitShell::Tcsh => "tcsh",
+        }
+    }
 }
@@ -6,4 +6,10 @@ pub struct Command {
     pub last_access_time: i64,
     /// Computed by the alman_score UDF; never stored in the DB.
     pub score: f64,
+    /// Runs recorded with a failing exit status.
+    pub failures: i64,
+    /// Privilege wrapper (`sudo`, `doas`) of its latest run, if any.
+    pub wrapper: Option<String>,
+    /// Runs inside the directory scope of a scoped query; 0 for global results.
+    pub local_frequency: i64,
 }
@@ -1,4 +1,4 @@
-use rusqlite::{Connection, Result, functions::FunctionFlags};
+use rusqlite::{Connection, OpenFlags, Result, functions::FunctionFlags};
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -23,6 +23,14 @@ pub fn get_db_path() -> String {
         .to_string()
 }
 
+/// Read-only open for the per-command `remind` check: no schema setup, so it
+/// fails if alman has not created the database yet.
+pub fn open_read_only(path: &str) -> Result<Connection> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.busy_timeout(std::time::Duration::from_millis(3000))?;
+    Ok(conn)
+}
+
 /// Lightweight open for the `custom` hot-write path.
 /// Sets pragmas and ensures the schema exists. No UDF, no migration, no bootstrap.
 pub fn open_for_write(path: &str) -> Result<Connection> {
@@ -38,17 +46,21 @@ pub fn open_for_write(path: &str) -> Result<Connection> {
              id         INTEGER PRIMARY KEY,
              command    TEXT    NOT NULL,
              ts         INTEGER NOT NULL,
-             session_id TEXT,
-             cwd        TEXT,
-             exit_code  INTEGER
+             session_id  TEXT,
+             cwd         TEXT,
+             exit_code   INTEGER,
+             duration_ms INTEGER
          );
          CREATE INDEX IF NOT EXISTS idx_events_session_ts ON events(session_id, ts);
+         CREATE INDEX IF NOT EXISTS idx_events_cwd ON events(cwd);
 
          CREATE TABLE IF NOT EXISTS command_stats (
              command_text     TEXT    PRIMARY KEY,
              frequency        INTEGER NOT NULL,
              last_access_time INTEGER NOT NULL,
-             length           INTEGER NOT NULL
+             length           INTEGER NOT NULL,
+             failures         INTEGER NOT NULL DEFAULT 0,
+             wrapper          TEXT
          );
 
          CREATE TABLE IF NOT EXISTS dismissed (
@@ -60,9 +72,37 @@ pub fn open_for_write(path: &str) -> Result<Connection> {
              name          TEXT    NOT NULL UNIQUE,
              kind          TEXT    NOT NULL,
              template_json TEXT    NOT NULL,
-             created_at    INTEGER NOT NULL
+             created_at    INTEGER NOT NULL,
+             bodies_json   TEXT,
+             value         TEXT
+         );
+
+         CREATE TABLE IF NOT EXISTS slot_values (
+             definition TEXT    NOT NULL,
+             slot       INTEGER NOT NULL,
+             value      TEXT    NOT NULL,
+             frequency  INTEGER NOT NULL,
+             PRIMARY KEY (definition, slot, value)
+         );
+
+         CREATE TABLE IF NOT EXISTS definition_usage (
+             definition TEXT    PRIMARY KEY,
+             uses       INTEGER NOT NULL,
+             last_used  INTEGER NOT NULL
+         );
+
+         CREATE TABLE IF NOT EXISTS reminders (
+             definition TEXT    PRIMARY KEY,
+             last_shown INTEGER NOT NULL
+         );
+
+         CREATE TABLE IF NOT EXISTS history_files (
+             path   TEXT    PRIMARY KEY,
+             inode  INTEGER NOT NULL,
+             offset INTEGER NOT NULL
          );",
     )?;
+    migrate_columns(&conn)?;
     Ok(conn)
 }
 
@@ -84,17 +124,21 @@ pub fn open(path: &str) -> Result<Connection> {
              id         INTEGER PRIMARY KEY,
              command    TEXT    NOT NULL,
              ts         INTEGER NOT NULL,
-             session_id TEXT,
-             cwd        TEXT,
-             exit_code  INTEGER
+             session_id  TEXT,
+             cwd         TEXT,
+             exit_code   INTEGER,
+             duration_ms INTEGER
          );
          CREATE INDEX IF NOT EXISTS idx_events_session_ts ON events(session_id, ts);
+         CREATE INDEX IF NOT EXISTS idx_events_cwd ON events(cwd);
 
          CREATE TABLE IF NOT EXISTS command_stats (
              command_text     TEXT    PRIMARY KEY,
              frequency        INTEGER NOT NULL,
              last_access_time INTEGER NOT NULL,
-             length           INTEGER NOT NULL
+             length           INTEGER NOT NULL,
+             failures         INTEGER NOT NULL DEFAULT 0,
+             wrapper          TEXT
          );
 
          CREATE TABLE IF NOT EXISTS dismissed (
@@ -106,22 +150,51 @@ pub fn open(path: &str) -> Result<Connection> {
              name          TEXT    NOT NULL UNIQUE,
              kind          TEXT    NOT NULL,
              template_json TEXT    NOT NULL,
-             created_at    INTEGER NOT NULL
+             created_at    INTEGER NOT NULL,
+             bodies_json   TEXT,
+             value         TEXT
+         );
+
+         CREATE TABLE IF NOT EXISTS slot_values (
+             definition TEXT    NOT NULL,
+             slot       INTEGER NOT NULL,
+             value      TEXT    NOT NULL,
+             frequency  INTEGER NOT NULL,
+             PRIMARY KEY (definition, slot, value)
+         );
+
+         CREATE TABLE IF NOT EXISTS definition_usage (
+             definition TEXT    PRIMARY KEY,
+             uses       INTEGER NOT NULL,
+             last_used  INTEGER NOT NULL
+         );
+
+         CREATE TABLE IF NOT EXISTS reminders (
+             definition TEXT    PRIMARY KEY,
+             last_shown INTEGER NOT NULL
+         );
+
+         CREATE TABLE IF NOT EXISTS history_files (
+             path   TEXT    PRIMARY KEY,
+             inode  INTEGER NOT NULL,
+             offset INTEGER NOT NULL
          );",
     )?;
+    migrate_columns(&conn)?;
 
-    // alman_score(frequency, last_access_time, length, now) -> f64
+    // alman_score(frequency, last_access_time, length, now, failures) -> f64
     let scorer = crate::defaults::default_relevance_scorer();
     conn.create_scalar_function(
         "alman_score",
-        4,
+        5,
         FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
         move |ctx| {
             let frequency: f64 = ctx.get::<i64>(0)? as f64;
             let last_access: i64 = ctx.get::<i64>(1)?;
             let length: f64 = ctx.get::<i64>(2)? as f64;
             let now: i64 = ctx.get::<i64>(3)?;
-            Ok(scorer.score(frequency, last_access, length, now))
+            let failures: f64 = ctx.get::<i64>(4)? as f64;
+            Ok(scorer.score(frequency, last_access, length, now, failures))
         },
     )?;
 
@@ -133,6 +206,29 @@ pub fn open(path: &str) -> Result<Connection> {
     Ok(conn)
 }
 
+/// Add columns introduced after the first release to databases created by an
+/// older schema. `CREATE TABLE IF NOT EXISTS` never alters an existing table.
+fn migrate_columns(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "events", "duration_ms", "INTEGER")?;
+    add_column_if_missing(conn, "command_stats", "failures", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "command_stats", "wrapper", "TEXT")?;
+    add_column_if_missing(conn, "definitions", "bodies_json", "TEXT")?;
+    add_column_if_missing(conn, "definitions", "value", "TEXT")?;
+    Ok(())
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists: bool = conn
+        .prepare(&format!("PRAGMA table_info({table})"))?
+        .query_map([], |r| r.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"))?;
+    }
+    Ok(())
+}
+
 /// One-time migration of existing alias files into the `definitions` table.
 /// Only runs while `definitions` is empty (idempotent).
 pub fn migrate_aliases_if_needed(conn: &Connection, alias_file_paths: &[String]) {
@@ -149,9 +245,7 @@ pub fn migrate_aliases_if_needed(conn: &Connection, alias_file_paths: &[String])
 
     let aliases = get_aliases_from_multiple_files(alias_file_paths);
     for (name, command) in aliases {
-        let template = CommandTemplate {
-            parts: vec![TemplatePart::Literal(command)],
-        };
+        let template = CommandTemplate::new(vec![TemplatePart::Literal(command)]);
         let _ = upsert_definition(conn, &name, DefinitionKind::Alias, &template);
     }
 }
@@ -300,4 +394,29 @@ mod tests {
             .unwrap();
         assert_eq!(count2, count1, "second migration must not change count");
     }
+
+    #[test]
+    fn open_adds_columns_missing_from_old_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.db").to_string_lossy().to_string();
+        {
+            let old = Connection::open(&path).unwrap();
+            old.execute_batch(
+                "CREATE TABLE events (id INTEGER PRIMARY KEY, command TEXT NOT NULL, ts INTEGER NOT NULL,
+                     session_id TEXT, cwd TEXT, exit_code INTEGER);
+                 CREATE TABLE command_stats (command_text TEXT PRIMARY KEY, frequency INTEGER NOT NULL,
+                     last_access_time INTEGER NOT NULL, length INTEGER NOT NULL);
+                 INSERT INTO command_stats VALUES ('git status', 3, 0, 10);",
+            )
+            .unwrap();
+        }
+
+        let conn = open_for_write(&path).unwrap();
+        let failures: i64 = conn
+            .query_row("SELECT failures FROM command_stats", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(failures, 0);
+        conn.execute("INSERT INTO events (command, ts, duration_ms) VALUES ('ls', 0, 5)", [])
+            .unwrap();
+    }
 }
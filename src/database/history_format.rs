@@ -0,0 +1,304 @@
+use std::path::Path;
+
+/// zsh's `Meta` byte: the byte after it is stored XOR 32 in the history file.
+const ZSH_META: u8 = 0x83;
+
+/// One command read back from a history file.
+#[derive(Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// When it ran, if the file records it.
+    pub ts: Option<i64>,
+}
+
+/// Reads one shell's history file format.
+pub trait HistoryParser: Send + Sync {
+    /// The entries in `content`, oldest first. Multi-line commands come back
+    /// as one entry with their newlines.
+    fn parse(&self, content: &[u8]) -> Vec<HistoryEntry>;
+
+    /// How much of `content` holds only finished entries, so a reader following
+    /// the file can leave an entry still being written for next time.
+    fn complete_len(&self, content: &[u8]) -> usize {
+        content.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1)
+    }
+}
+
+/// Binary history files, by their first bytes, with the shell that writes them.
+const BINARY_MAGIC: &[(&[u8], &str)] = &[(&[0x81, 0x01], "ksh93"), (&[0xab, 0xcd], "mksh")];
+
+/// The shell whose binary history `header` (a file's first bytes) starts, if
+/// it is one of the formats that are not lines of text and cannot be read.
+pub fn binary_history_shell(header: &[u8]) -> Option<&'static str> {
+    BINARY_MAGIC
+        .iter()
+        .find(|(magic, _)| header.starts_with(magic))
+        .map(|(_, shell)| *shell)
+}
+
+/// The history file formats alman can read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryFormat {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Which parser reads `path`: by file name when it names a shell, otherwise by
+/// what the content looks like, with bash's plain lines as the fallback.
+pub fn detect(path: &Path, content: &[u8]) -> HistoryFormat {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if name.contains("fish") {
+        return HistoryFormat::Fish;
+    }
+    if name.contains("zsh") {
+        return HistoryFormat::Zsh;
+    }
+    if name.contains("bash") {
+        return HistoryFormat::Bash;
+    }
+    let text = String::from_utf8_lossy(content);
+    if text.lines().any(|l| l.starts_with("- cmd:")) {
+        HistoryFormat::Fish
+    } else if content.contains(&ZSH_META) || text.lines().any(|l| zsh_extended_line(l).is_some()) {
+        HistoryFormat::Zsh
+    } else {
+        HistoryFormat::Bash
+    }
+}
+
+/// `.bash_history`, and the plain one-command-per-line files of sh and ksh.
+///
+/// With `HISTTIMEFORMAT` set, bash writes a `#1699999999` line before each
+/// entry and keeps a multi-line command's lines together up to the next one.
+/// Without timestamps every line is its own entry and `#` lines are comments.
+pub struct BashHistory;
+
+impl HistoryParser for BashHistory {
+    fn parse(&self, content: &[u8]) -> Vec<HistoryEntry> {
+        let text = String::from_utf8_lossy(content);
+        let timestamped = text.lines().any(|l| bash_timestamp(l).is_some());
+        let mut entries: Vec<HistoryEntry> = Vec::new();
+        // Whether the current timestamped entry has lines yet.
+        let mut open = false;
+        for line in text.lines() {
+            if let Some(ts) = bash_timestamp(line) {
+                entries.push(HistoryEntry { command: String::new(), ts: Some(ts) });
+                open = true;
+                continue;
+            }
+            if timestamped && open {
+                let entry = entries.last_mut().expect("opened by a timestamp");
+                if !entry.command.is_empty() {
+                    entry.command.push('\n');
+                }
+                entry.command.push_str(line);
+            } else if !line.trim_start().starts_with('#') {
+                entries.push(HistoryEntry { command: line.to_string(), ts: None });
+            }
+        }
+        entries
+    }
+}
+
+/// `#1699999999`, bash's timestamp line.
+fn bash_timestamp(line: &str) -> Option<i64> {
+    line.strip_prefix('#').and_then(parse_timestamp)
+}
+
+/// `.zsh_history`, plain or with `EXTENDED_HISTORY` (`: 1699999999:0;cmd`).
+///
+/// zsh stores the newlines of a multi-line command as a trailing backslash and
+/// metafies the bytes it reserves for itself (0x83 and up) as `0x83, byte ^ 32`.
+pub struct ZshHistory;
+
+impl HistoryParser for ZshHistory {
+    fn parse(&self, content: &[u8]) -> Vec<HistoryEntry> {
+        let text = String::from_utf8_lossy(&unmetafy(content)).into_owned();
+        let mut entries = Vec::new();
+        let mut lines = text.lines();
+        while let Some(first) = lines.next() {
+            let (ts, first) = match zsh_extended_line(first) {
+                Some((ts, command)) => (Some(ts), command),
+                None => (None, first),
+            };
+            let mut command = first.to_string();
+            while command.ends_with('\\') {
+                command.pop();
+                command.push('\n');
+                match lines.next() {
+                    Some(next) => command.push_str(next),
+                    None => break,
+                }
+            }
+            entries.push(HistoryEntry { command, ts });
+        }
+        entries
+    }
+
+    fn complete_len(&self, content: &[u8]) -> usize {
+        // A line ending in a backslash continues on the next one.
+        let mut end = content.len();
+        while let Some(i) = content[..end].iter().rposition(|&b| b == b'\n') {
+            if i == 0 || content[i - 1] != b'\\' {
+                return i + 1;
+            }
+            end = i;
+        }
+        0
+    }
+}
+
+/// The start time and command of a zsh extended-history line.
+fn zsh_extended_line(line: &str) -> Option<(i64, &str)> {
+    let rest = line.strip_prefix(": ")?;
+    let (meta, command) = rest.split_once(';')?;
+    let (start, elapsed) = meta.split_once(':')?;
+    elapsed.trim().parse::<u64>().ok()?;
+    Some((parse_timestamp(start)?, command))
+}
+
+/// Undo zsh's metafication.
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len());
+    let mut bytes = content.iter();
+    while let Some(&b) = bytes.next() {
+        if b == ZSH_META {
+            if let Some(&next) = bytes.next() {
+                out.push(next ^ 32);
+            }
+        } else {
+            out.push(b);
+        }
+    }
+    out
+}
+
+/// fish's YAML-like `fish_history`:
+///
+/// ```text
+/// - cmd: git commit -m 'two\nlines'
+///   when: 1699999999
+///   paths:
+///     - src
+/// ```
+///
+/// Newlines and backslashes in `cmd` are escaped as `\n` and `\\`.
+pub struct FishHistory;
+
+impl HistoryParser for FishHistory {
+    fn parse(&self, content: &[u8]) -> Vec<HistoryEntry> {
+        let text = String::from_utf8_lossy(content);
+        let mut entries: Vec<HistoryEntry> = Vec::new();
+        for line in text.lines() {
+            if let Some(command) = line.strip_prefix("- cmd:") {
+                entries.push(HistoryEntry { command: unescape_fish(command.trim_start()), ts: None });
+            } else if let Some(field) = line.strip_prefix("  ").filter(|f| !f.starts_with(' ')) {
+                if let (Some(entry), Some(when)) = (entries.last_mut(), field.strip_prefix("when:")) {
+                    entry.ts = parse_timestamp(when);
+                }
+            }
+        }
+        entries
+    }
+}
+
+/// Decode fish's `\n` and `\\`; other backslashes are kept as written.
+fn unescape_fish(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Seconds since the epoch, as history files write them.
+fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok().filter(|&ts| ts > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(command: &str, ts: Option<i64>) -> HistoryEntry {
+        HistoryEntry { command: command.to_string(), ts }
+    }
+
+    #[test]
+    fn bash_joins_lines_between_timestamps() {
+        let plain = "ls -la /tmp\n# a comment\nmake test\n";
+        assert_eq!(
+            BashHistory.parse(plain.as_bytes()),
+            vec![entry("ls -la /tmp", None), entry("make test", None)]
+        );
+
+        let stamped = "#1699999100\nfor f in *.log; do\n  gzip \"$f\"\ndone\n#1699999200\nmake test\n";
+        assert_eq!(
+            BashHistory.parse(stamped.as_bytes()),
+            vec![
+                entry("for f in *.log; do\n  gzip \"$f\"\ndone", Some(1_699_999_100)),
+                entry("make test", Some(1_699_999_200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn zsh_reassembles_continuations_and_unmetafies() {
+        let mut content = b": 1699999000:0;git status\n: 1699999100:2;docker run \\\n  --rm alpine\necho ".to_vec();
+        // "é" is 0xC3 0xA9; zsh metafies 0xA9 as 0x83 0x89.
+        content.extend_from_slice(&[0xC3, ZSH_META, 0xA9 ^ 32]);
+        content.push(b'\n');
+        assert_eq!(
+            ZshHistory.parse(&content),
+            vec![
+                entry("git status", Some(1_699_999_000)),
+                entry("docker run \n  --rm alpine", Some(1_699_999_100)),
+                entry("echo é", None),
+            ]
+        );
+
+        let unfinished = b": 1699999000:0;git status\n: 1699999100:0;make \\\n";
+        assert_eq!(ZshHistory.complete_len(unfinished), 26);
+    }
+
+    #[test]
+    fn fish_decodes_escapes_and_reads_fields() {
+        let content = "- cmd: git commit -m 'one\\ntwo'\n  when: 1699999300\n  paths:\n    - src\n- cmd: echo C:\\\\Users\\\\me\n  when: 1699999400\n";
+        assert_eq!(
+            FishHistory.parse(content.as_bytes()),
+            vec![
+                entry("git commit -m 'one\ntwo'", Some(1_699_999_300)),
+                entry("echo C:\\Users\\me", Some(1_699_999_400)),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_format_by_name_then_content() {
+        assert_eq!(detect(Path::new("/h/.zsh_history"), b""), HistoryFormat::Zsh);
+        assert_eq!(detect(Path::new("/h/fish_history"), b""), HistoryFormat::Fish);
+        assert_eq!(detect(Path::new("/h/.histfile"), b": 1699999000:0;ls\n"), HistoryFormat::Zsh);
+        assert_eq!(detect(Path::new("/h/.history"), b"- cmd: ls\n"), HistoryFormat::Fish);
+        assert_eq!(detect(Path::new("/h/.history"), b"ls -la\n"), HistoryFormat::Bash);
+        assert_eq!(binary_history_shell(&[0x81, 0x01, b'l', b's']), Some("ksh93"));
+        assert_eq!(binary_history_shell(b"ls -la\n"), None);
+    }
+}
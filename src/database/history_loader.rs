@@ -4,8 +4,15 @@ use std::fs;
 use std::path::Path;
 
 use super::db::now_secs;
+use super::history_format::{self, HistoryParser};
+use crate::ast::{CommandList, Token};
+use crate::registry::DefinitionKind;
+use crate::tokenize::{is_plain_word, split_flags, tokens_to_text, Arg};
+use std::collections::HashSet;
 
 const MAX_PREFIX_WORDS: usize = 3;
+/// Longer pipeline tails are one-offs rather than reusable filters.
+const MAX_TAIL_TOKENS: usize = 12;
 const COMPACT_STALE_SECS: i64 = 90 * 86_400;   // 90 days
 const COMPACT_EVENTS_SECS: i64 = 365 * 86_400;  // 365 days
 
@@ -30,15 +37,26 @@ pub fn bootstrap_from_history(conn: &Connection) {
         return;
     }
 
-    let content = match fs::read_to_string(&history_file) {
-        Ok(c) => c,
-        Err(_) => match fs::read(&history_file) {
-            Ok(b) => String::from_utf8_lossy(&b).into_owned(),
-            Err(_) => return,
-        },
+    let content = match fs::read(&history_file) {
+        Ok(b) => b,
+        Err(_) => return,
     };
 
-    let commands = parse_history_file(&content);
+    if history_format::binary_history_shell(&content).is_some() {
+        return;
+    }
+
+    // Later `sync-history` calls start where this read ended.
+    crate::ops::sync_history::mark_history_file(conn, Path::new(&history_file));
+
+    let format = history_format::detect(Path::new(&history_file), &content);
+    let commands = parse_history_file(&*crate::defaults::history_parser_for(format), &content);
+    seed_from_history(conn, &commands);
+}
+
+/// Record `commands`, newest first as [`parse_history_file`] returns them, as
+/// events and `command_stats` rows.
+fn seed_from_history(conn: &Connection, commands: &[(String, Option<i64>)]) {
     if commands.is_empty() {
         return;
     }
@@ -48,15 +66,18 @@ pub fn bootstrap_from_history(conn: &Connection) {
         .and_then(|p| Path::new(&p).file_name().map(|f| f.to_os_string()))
         .and_then(|s| s.into_string().ok());
 
-    let base_ts = now_secs();
+    // Commands without a recorded time are spaced `interval` apart, counting back
+    // from the next newer one.
     let interval = 120_i64;
+    let mut ts = now_secs() + interval;
 
     let tx = match conn.unchecked_transaction() {
         Ok(t) => t,
         Err(_) => return,
     };
 
-    for (i, command) in commands.iter().enumerate() {
+    for (command, recorded) in commands {
+        ts = recorded.unwrap_or(ts - interval);
         let cmd = command.trim();
         if cmd.is_empty() || cmd.len() <= 2 {
             continue;
@@ -71,37 +92,109 @@ pub fn bootstrap_from_history(conn: &Connection) {
             }
         }
 
-        let ts = base_ts - (i as i64 * interval);
-
         let _ = tx.execute(
             "INSERT INTO events (command, ts, session_id, cwd, exit_code) VALUES (?1, ?2, NULL, NULL, NULL)",
             rusqlite::params![cmd, ts],
         );
 
-        upsert_prefixes(&tx, cmd, ts);
+        upsert_prefixes(&tx, cmd, ts, false);
     }
 
     let _ = tx.commit();
 }
 
-/// Insert or increment `command_stats` rows for a command and its word-prefixes.
+/// A `command_stats` row touched by a command.
+#[derive(Debug, PartialEq)]
+pub struct CommandPrefix {
+    pub text: String,
+    pub length: i64,
+    /// Privilege wrapper the command ran under, e.g. `sudo`.
+    pub wrapper: Option<String>,
+}
+
+/// The word-prefixes of a command that get a `command_stats` row, with their lengths.
 ///
-/// Stops before the first flag token (starting with `-`) and caps at
-/// `MAX_PREFIX_WORDS` words so quoted arguments never produce junk rows.
-pub fn upsert_prefixes(conn: &Connection, full_cmd: &str, ts: i64) {
-    let parts: Vec<String> = crate::defaults::default_tokenizer().tokenize(full_cmd);
+/// Each pipeline segment and each `&&`/`;`-chained command contributes its own
+/// prefixes, so `journalctl -u nginx | tail -n 50` also counts `tail -n 50`.
+/// Wrappers are stripped first (`sudo systemctl restart` counts as `systemctl
+/// restart` under wrapper `sudo`). A flag and its value count as one word, so
+/// `kubectl logs -n prod pod-a` yields `kubectl logs -n prod`. Prefixes stop at the
+/// first token that would need quoting and cap at `MAX_PREFIX_WORDS` words so
+/// quoted arguments never produce junk rows.
+///
+/// Pipeline tails (`grep -v grep | awk '{print $2}'`) are counted whole, quoted
+/// where needed, since a filter reused across different producers is a candidate
+/// of its own.
+pub fn command_prefixes(full_cmd: &str) -> Vec<CommandPrefix> {
+    command_prefixes_except(full_cmd, |_| false)
+}
+
+/// [`command_prefixes`], leaving out the commands and pipeline tails whose
+/// typed first word `skip` accepts; the rest of the line still counts.
+pub fn command_prefixes_except(full_cmd: &str, skip: impl Fn(&str) -> bool) -> Vec<CommandPrefix> {
+    let tokens = crate::defaults::default_tokenizer().lex(full_cmd);
+    let starts_skipped = |tokens: &[Token]| tokens.first().and_then(Token::as_word).is_some_and(&skip);
+    let Some(list) = CommandList::parse(&tokens) else {
+        if starts_skipped(&tokens) {
+            return Vec::new();
+        }
+        return word_prefixes(&tokens, None);
+    };
+    let normalizer = crate::defaults::default_normalizer();
+
+    let mut seen = HashSet::new();
+    let mut prefixes = Vec::new();
+    for command in list.simple_commands() {
+        if command.words.first().is_some_and(|w| skip(w)) {
+            continue;
+        }
+        let unwrapped = normalizer.unwrap(&command.words);
+        let words: Vec<Token> = unwrapped.words.into_iter().map(Token::Word).collect();
+        for prefix in word_prefixes(&words, unwrapped.elevation) {
+            if seen.insert(prefix.text.clone()) {
+                prefixes.push(prefix);
+            }
+        }
+    }
+    for tail in list.pipeline_tails() {
+        let tokens = tail.to_tokens();
+        if starts_skipped(&tokens) {
+            continue;
+        }
+        let words: Vec<&str> = tokens.iter().filter_map(|t| t.as_word()).collect();
+        let length = words.iter().map(|w| w.len()).sum::<usize>() as i64;
+        if tokens.len() > MAX_TAIL_TOKENS || (words.len() == 1 && length <= 5) {
+            continue;
+        }
+        let text = tokens_to_text(&tokens);
+        if seen.insert(text.clone()) {
+            prefixes.push(CommandPrefix { text, length, wrapper: None });
+        }
+    }
+    prefixes
+}
+
+/// Prefixes of one command's words, up to the first operator.
+fn word_prefixes(tokens: &[Token], wrapper: Option<String>) -> Vec<CommandPrefix> {
+    let mut prefixes = Vec::new();
     let mut temp = String::new();
     let mut word_count = 0usize;
 
-    for word in &parts {
-        if word.starts_with('-') {
+    for arg in split_flags(tokens) {
+        if let Arg::Op(_) = arg {
+            break;
+        }
+        let tokens = arg.tokens();
+        if !tokens.iter().all(|t| is_plain_word(t)) {
             break;
         }
 
-        if !temp.is_empty() {
-            temp.push(' ');
+        for token in tokens {
+            if !temp.is_empty() {
+                temp.push(' ');
+            }
+            temp.push_str(token);
         }
-        temp.push_str(word);
         word_count += 1;
 
         let length: i64 = temp.split_whitespace().map(|s| s.len()).sum::<usize>() as i64;
@@ -110,19 +203,53 @@ pub fn upsert_prefixes(conn: &Connection, full_cmd: &str, ts: i64) {
             continue;
         }
 
+        prefixes.push(CommandPrefix { text: temp.clone(), length, wrapper: wrapper.clone() });
+
+        if word_count >= MAX_PREFIX_WORDS {
+            break;
+        }
+    }
+    prefixes
+}
+
+/// Insert or increment `command_stats` rows for a command and its word-prefixes.
+/// When `failed` is set, every touched row also has its `failures` counter bumped.
+/// A row keeps the latest time it was seen, whatever order commands arrive in,
+/// and the privilege wrapper of that latest run. A command in the line whose
+/// first word runs a saved alias or function is a use of that definition, not
+/// a suggestion, unless the definition just runs the program of the same name;
+/// the other commands of the line still count.
+pub fn upsert_prefixes(conn: &Connection, full_cmd: &str, ts: i64, failed: bool) {
+    let failed = failed as i64;
+    for prefix in command_prefixes_except(full_cmd, |first| runs_definition(conn, first)) {
         let _ = conn.execute(
-            "INSERT INTO command_stats (command_text, frequency, last_access_time, length)
-             SELECT ?1, 1, ?2, ?3
+            "INSERT INTO command_stats (command_text, frequency, last_access_time, length, failures, wrapper)
+             SELECT ?1, 1, ?2, ?3, ?4, ?5
              WHERE ?1 NOT IN (SELECT command_text FROM dismissed)
              ON CONFLICT(command_text) DO UPDATE SET
                frequency        = frequency + 1,
-               last_access_time = excluded.last_access_time",
-            rusqlite::params![temp, ts, length],
+               last_access_time = MAX(last_access_time, excluded.last_access_time),
+               failures         = failures + excluded.failures,
+               wrapper          = CASE WHEN excluded.last_access_time >= last_access_time
+                                      THEN excluded.wrapper ELSE wrapper END",
+            rusqlite::params![prefix.text, ts, prefix.length, failed, prefix.wrapper],
         );
+    }
+}
 
-        if word_count >= MAX_PREFIX_WORDS {
-            break;
-        }
+/// Whether `first`, typed as a command's first word, invoked a saved alias or
+/// function that runs something other than the program it shadows, as `gs` for
+/// `git status` does and `ls` for `ls --color=auto` does not.
+fn runs_definition(conn: &Connection, first: &str) -> bool {
+    match crate::registry::find_definition(conn, first) {
+        Ok(Some(def)) => match def.kind {
+            DefinitionKind::Env(_) => false,
+            DefinitionKind::Raw(_) => true,
+            DefinitionKind::Alias | DefinitionKind::Function => {
+                def.template.program().as_deref() != Some(first)
+            }
+        },
+        _ => false,
     }
 }
 
@@ -144,7 +271,7 @@ pub fn compact(conn: &Connection) {
 // History file parsing
 // ---------------------------------------------------------------------------
 
-fn get_history_file_path() -> Result<String, Box<dyn std::error::Error>> {
+pub fn get_history_file_path() -> Result<String, Box<dyn std::error::Error>> {
     if let Ok(histfile) = env::var("HISTFILE") {
         if !histfile.is_empty() {
             return Ok(histfile);
@@ -169,51 +296,30 @@ fn get_history_file_path() -> Result<String, Box<dyn std::error::Error>> {
     Err("No history file found".into())
 }
 
-fn parse_history_file(content: &str) -> Vec<String> {
+/// Commands in a history file's content, newest first, each with the time it
+/// ran when the file records one. `parser` reads the file's format; see
+/// [`history_format::detect`].
+pub fn parse_history_file(parser: &dyn HistoryParser, content: &[u8]) -> Vec<(String, Option<i64>)> {
     let mut commands = Vec::new();
 
-    for line in content.lines().rev() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() { continue; }
-        if trimmed.starts_with('#')
-            || trimmed.starts_with("HISTTIMEFORMAT")
-            || trimmed.starts_with("HISTSIZE")
-            || trimmed.starts_with("HISTFILESIZE")
+    for entry in parser.parse(content).into_iter().rev() {
+        let command = entry.command.trim();
+        if command.is_empty()
+            || command.starts_with('#')
+            || command.starts_with("HISTTIMEFORMAT")
+            || command.starts_with("HISTSIZE")
+            || command.starts_with("HISTFILESIZE")
         {
             continue;
         }
-
-        let command = extract_command(trimmed);
-        if !command.is_empty() && command.len() > 2 {
-            commands.push(command);
+        if command.len() > 2 {
+            commands.push((command.to_string(), entry.ts));
         }
     }
 
     commands
 }
 
-fn extract_command(line: &str) -> String {
-    if line.starts_with(": ") {
-        if let Some(pos) = line.find(';') {
-            let cmd = line[pos + 1..].trim();
-            if !cmd.is_empty() { return cmd.to_string(); }
-        }
-        return String::new();
-    }
-
-    if let Some(stripped) = line.strip_prefix("- cmd:") {
-        let cmd = stripped.trim();
-        if !cmd.is_empty() { return cmd.to_string(); }
-        return String::new();
-    }
-
-    if line.starts_with('#') {
-        return String::new();
-    }
-
-    line.to_string()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,17 +331,17 @@ mod tests {
     }
 
     #[test]
-    fn upsert_prefixes_stops_at_flag() {
+    fn upsert_prefixes_stops_before_a_flag_whose_value_needs_quoting() {
         let conn = open_raw();
-        upsert_prefixes(&conn, "git commit -m 'fix bug'", 1_000_000);
+        upsert_prefixes(&conn, "git commit -m 'fix bug'", 1_000_000, false);
 
         let rows: Vec<String> = {
             let mut stmt = conn.prepare("SELECT command_text FROM command_stats").unwrap();
             stmt.query_map([], |r| r.get(0)).unwrap().filter_map(|r| r.ok()).collect()
         };
-        // Should have "git commit" only — stops before "-m"
+        // Should have "git commit" only — stops before "-m", whose value needs quoting
         assert!(rows.iter().any(|r| r == "git commit"), "expected 'git commit'");
-        // No word in any row should start with '-' (flag tokens must not be stored)
+        // `-m` counts as one word with its value, so it is left out along with it
         for row in &rows {
             for word in row.split_whitespace() {
                 assert!(!word.starts_with('-'),
@@ -246,10 +352,72 @@ mod tests {
             "quoted arg must not appear as separate token");
     }
 
+    #[test]
+    fn prefixes_keep_flag_value_pairs() {
+        let prefixes: Vec<String> = command_prefixes("kubectl logs -n prod pod-a")
+            .into_iter()
+            .map(|p| p.text)
+            .collect();
+        assert_eq!(prefixes, vec!["kubectl", "kubectl logs", "kubectl logs -n prod"]);
+    }
+
+    #[test]
+    fn prefixes_cover_each_segment_and_pipeline_tails() {
+        let prefixes: Vec<String> = command_prefixes("journalctl -u nginx | tail -n 50")
+            .into_iter()
+            .map(|p| p.text)
+            .collect();
+        assert_eq!(prefixes, vec!["journalctl", "journalctl -u nginx", "tail -n 50"]);
+
+        let prefixes: Vec<String> = command_prefixes("ps aux | grep -v grep | awk '{print $2}' && make test")
+            .into_iter()
+            .map(|p| p.text)
+            .collect();
+        assert!(prefixes.contains(&"make test".to_string()), "{prefixes:?}");
+        assert!(prefixes.contains(&"grep -v grep".to_string()), "{prefixes:?}");
+        assert!(
+            prefixes.contains(&"grep -v grep | awk '{print $2}'".to_string()),
+            "{prefixes:?}"
+        );
+        assert!(prefixes.contains(&"awk '{print $2}'".to_string()), "{prefixes:?}");
+    }
+
+    #[test]
+    fn upsert_prefixes_aggregates_on_the_unwrapped_command() {
+        let conn = open_raw();
+        upsert_prefixes(&conn, "systemctl restart nginx", 1_000_000, false);
+        upsert_prefixes(&conn, "time FOO=1 systemctl restart nginx", 1_000_001, false);
+        upsert_prefixes(&conn, "sudo systemctl restart nginx", 1_000_002, false);
+        // Seen out of order, as when seeding from history: an older run without
+        // sudo does not replace the wrapper of the latest one.
+        upsert_prefixes(&conn, "systemctl restart nginx", 999_999, false);
+
+        let (freq, wrapper): (i64, Option<String>) = conn
+            .query_row(
+                "SELECT frequency, wrapper FROM command_stats WHERE command_text = 'systemctl restart nginx'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((freq, wrapper.as_deref()), (4, Some("sudo")));
+
+        upsert_prefixes(&conn, "systemctl restart nginx", 1_000_003, false);
+        let wrapper: Option<String> = conn
+            .query_row("SELECT wrapper FROM command_stats WHERE command_text = 'systemctl restart nginx'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(wrapper, None);
+        let sudo_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM command_stats WHERE command_text LIKE 'sudo%'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sudo_rows, 0);
+    }
+
     #[test]
     fn upsert_prefixes_caps_at_max_words() {
         let conn = open_raw();
-        upsert_prefixes(&conn, "one two three four five", 1_000_000);
+        upsert_prefixes(&conn, "one two three four five", 1_000_000, false);
 
         let rows: Vec<String> = {
             let mut stmt = conn.prepare("SELECT command_text FROM command_stats").unwrap();
@@ -263,6 +431,81 @@ mod tests {
         assert!(!rows.iter().any(|r| r == "one two three four"), "4-word prefix must not exist");
     }
 
+    #[test]
+    fn upsert_prefixes_counts_failures() {
+        let conn = open_raw();
+        upsert_prefixes(&conn, "cargo build --release", 1_000_000, false);
+        upsert_prefixes(&conn, "cargo build --release", 1_000_001, true);
+
+        let (freq, failures): (i64, i64) = conn
+            .query_row(
+                "SELECT frequency, failures FROM command_stats WHERE command_text = 'cargo build'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((freq, failures), (2, 1));
+    }
+
+    #[test]
+    fn alias_shadowing_its_own_program_still_counts() {
+        let conn = open_raw();
+        crate::ops::apply::apply_add(&conn, "ls", "ls --color=auto").unwrap();
+        crate::ops::apply::apply_add(&conn, "gs", "git status").unwrap();
+        for ts in 1_000_000..1_000_003 {
+            upsert_prefixes(&conn, "ls -la /tmp", ts, false);
+            upsert_prefixes(&conn, "gs --short", ts, false);
+        }
+
+        let freq: i64 = conn
+            .query_row(
+                "SELECT frequency FROM command_stats WHERE command_text = 'ls -la /tmp'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(freq, 3);
+        let gs_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM command_stats WHERE command_text LIKE 'gs%'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(gs_rows, 0, "a use of `gs` is not a suggestion");
+    }
+
+    #[test]
+    fn a_definition_use_leaves_the_rest_of_the_line_counted() {
+        let conn = open_raw();
+        crate::ops::apply::apply_add(&conn, "gs", "git status").unwrap();
+        upsert_prefixes(&conn, "gs && make test", 1_000_000, false);
+        upsert_prefixes(&conn, "gs | grep modified", 1_000_001, false);
+        upsert_prefixes(&conn, "cd /srv && gs --short", 1_000_002, false);
+
+        let rows: Vec<String> = {
+            let mut stmt = conn.prepare("SELECT command_text FROM command_stats").unwrap();
+            stmt.query_map([], |r| r.get(0)).unwrap().filter_map(|r| r.ok()).collect()
+        };
+        assert!(rows.contains(&"make test".to_string()), "{rows:?}");
+        assert!(rows.contains(&"grep modified".to_string()), "{rows:?}");
+        assert!(rows.contains(&"cd /srv".to_string()), "{rows:?}");
+        assert!(!rows.iter().any(|r| r.starts_with("gs")), "{rows:?}");
+    }
+
+    #[test]
+    fn seeding_keeps_the_latest_time_of_a_repeated_command() {
+        let conn = open_raw();
+        let history = b": 1500000000:0;git status --short\n: 1600000000:0;make test\n: 1700000000:0;git status --short\n";
+        let commands = parse_history_file(&history_format::ZshHistory, history);
+        seed_from_history(&conn, &commands);
+
+        let (freq, last): (i64, i64) = conn
+            .query_row(
+                "SELECT frequency, last_access_time FROM command_stats WHERE command_text = 'git status --short'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((freq, last), (2, 1_700_000_000));
+    }
+
     #[test]
     fn bootstrap_guard_skips_when_stats_populated() {
         // Use a raw connection (no bootstrap) so we control initial state.
@@ -1,5 +1,6 @@
 pub mod database_structs;
 pub mod db;
 pub mod persistence;
+pub mod history_format;
 pub mod history_loader;
 pub mod scoring;
@@ -6,6 +6,23 @@ pub const CONFIG_FILE: &str = "config.json";
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct AppConfig {
     pub alias_file_paths: Vec<String>,
+    /// Print a hint when a typed command could have used a saved alias.
+    #[serde(default = "default_true")]
+    pub reminders: bool,
+    /// Shell hooks record commands as they run. Without them the history file
+    /// is synced at TUI launch and `get-suggestions` instead.
+    #[serde(default = "default_true")]
+    pub hooks: bool,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self { alias_file_paths: vec![get_default_alias_file_path()], reminders: true, hooks: true }
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
 pub fn save_config(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
@@ -1,4 +1,4 @@
-/// Query-time relevance score — mirrors the `alman_score` SQLite UDF.
+/// Query-time relevance score — the base of the `alman_score` SQLite UDF.
 ///
 /// Recency multipliers:
 ///   ≤ 1 h  → 4.0 | ≤ 24 h → 2.0 | ≤ 7 d → 0.5 | older → 0.25
@@ -16,15 +16,26 @@ pub fn score(frequency: f64, last_access: i64, length: f64, now: i64) -> f64 {
     mult * length.powf(0.6) * frequency
 }
 
+/// Fraction of recorded runs that succeeded, used to damp commands that mostly fail.
+///
+/// Runs with an unknown exit status (history bootstrap, older hooks) count as successes.
+pub fn success_weight(frequency: f64, failures: f64) -> f64 {
+    if frequency <= 0.0 {
+        return 1.0;
+    }
+    ((frequency - failures) / frequency).clamp(0.0, 1.0)
+}
+
 pub trait RelevanceScorer: Send + Sync {
-    fn score(&self, frequency: f64, last_access: i64, length: f64, now: i64) -> f64;
+    fn score(&self, frequency: f64, last_access: i64, length: f64, now: i64, failures: f64) -> f64;
 }
 
 pub struct RecencyFrequencyScorer;
 
 impl RelevanceScorer for RecencyFrequencyScorer {
-    fn score(&self, frequency: f64, last_access: i64, length: f64, now: i64) -> f64 {
+    fn score(&self, frequency: f64, last_access: i64, length: f64, now: i64, failures: f64) -> f64 {
         crate::database::scoring::score(frequency, last_access, length, now)
+            * success_weight(frequency, failures)
     }
 }
 
@@ -99,6 +110,15 @@ mod tests {
         assert!(at > just_over, "boundary at 86400 s violated");
     }
 
+    #[test]
+    fn failures_damp_score() {
+        let scorer = RecencyFrequencyScorer;
+        let clean = scorer.score(10.0, NOW - 60, 10.0, NOW, 0.0);
+        let flaky = scorer.score(10.0, NOW - 60, 10.0, NOW, 8.0);
+        assert!(flaky < clean, "mostly-failing command must rank lower");
+        assert_eq!(scorer.score(4.0, NOW - 60, 10.0, NOW, 4.0), 0.0);
+    }
+
     #[test]
     fn bucket_boundary_604800() {
         let at = s(1.0, 604_800, 10.0);
@@ -1,12 +1,26 @@
 use crate::cli::cli_data::InitShell;
+use crate::database::history_format::{
+    BashHistory, FishHistory, HistoryFormat, HistoryParser, ZshHistory,
+};
 use crate::database::scoring::{RecencyFrequencyScorer, RelevanceScorer};
-use crate::mining::miner::{FixedArityMiner, TemplateMiner};
+use crate::mining::miner::{CompositeMiner, FixedArityMiner, TemplateMiner};
+use crate::mining::prefix::PrefixMiner;
+use crate::mining::sequence::SequenceMiner;
 use crate::mining::scorer::{SavingsScorer, TemplateScorer};
-use crate::render::{FishRenderer, PosixRenderer, ShellRenderer};
-use crate::tokenize::{ShlexTokenizer, Tokenizer};
+use crate::normalize::{Normalizer, WrapperNormalizer};
+use crate::registry::Definition;
+use crate::render::completion::{BashCompletion, CompletionRenderer, FishCompletion, ZshCompletion};
+use crate::render::{
+    FishRenderer, NuRenderer, PosixRenderer, PwshRenderer, ShellRenderer, TcshRenderer,
+};
+use crate::tokenize::{ShellTokenizer, Tokenizer};
 
 pub fn default_tokenizer() -> Box<dyn Tokenizer> {
-    Box::new(ShlexTokenizer)
+    Box::new(ShellTokenizer)
+}
+
+pub fn default_normalizer() -> Box<dyn Normalizer> {
+    Box::new(WrapperNormalizer)
 }
 
 pub fn default_relevance_scorer() -> Box<dyn RelevanceScorer> {
@@ -18,12 +32,43 @@ pub fn default_template_scorer() -> Box<dyn TemplateScorer> {
 }
 
 pub fn default_miner() -> Box<dyn TemplateMiner> {
-    Box::new(FixedArityMiner::new(default_template_scorer()))
+    Box::new(CompositeMiner::new(vec![
+        Box::new(FixedArityMiner::new(default_template_scorer())),
+        Box::new(PrefixMiner::new(default_template_scorer())),
+        Box::new(SequenceMiner::new(default_template_scorer())),
+    ]))
+}
+
+pub fn history_parser_for(format: HistoryFormat) -> Box<dyn HistoryParser> {
+    match format {
+        HistoryFormat::Bash => Box::new(BashHistory),
+        HistoryFormat::Zsh => Box::new(ZshHistory),
+        HistoryFormat::Fish => Box::new(FishHistory),
+    }
 }
 
-pub fn renderer_for(shell: &InitShell) -> Box<dyn ShellRenderer> {
+/// The renderer for `shell`, told the names of all saved `definitions` so it
+/// can tell calls to them from programs.
+pub fn renderer_for(shell: &InitShell, definitions: &[Definition]) -> Box<dyn ShellRenderer> {
     match shell {
         InitShell::Fish => Box::new(FishRenderer),
+        InitShell::Nu => Box::new(NuRenderer {
+            commands: definitions.iter().filter(|d| !d.kind.is_env()).map(|d| d.name.clone()).collect(),
+        }),
+        InitShell::Pwsh => Box::new(PwshRenderer),
+        InitShell::Tcsh => Box::new(TcshRenderer),
         _ => Box::new(PosixRenderer),
     }
 }
+
+/// Completion output for `shell`; plain POSIX shells have no completion system,
+/// nushell completes a `def`'s parameters on its own, and PowerShell and tcsh
+/// fall back to completing paths.
+pub fn completion_renderer_for(shell: &InitShell) -> Option<Box<dyn CompletionRenderer>> {
+    match shell {
+        InitShell::Bash => Some(Box::new(BashCompletion)),
+        InitShell::Zsh => Some(Box::new(ZshCompletion)),
+        InitShell::Fish => Some(Box::new(FishCompletion)),
+        InitShell::Posix | InitShell::Nu | InitShell::Pwsh | InitShell::Tcsh => None,
+    }
+}
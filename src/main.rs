@@ -4,25 +4,28 @@ mod ops;
 mod tui;
 mod shell;
 mod template;
+mod ast;
 mod tokenize;
+mod normalize;
 mod defaults;
 mod registry;
 mod mining;
 mod render;
 
 use cli::arg_handler::parse_args;
-use cli::cli_data::Operation;
+use cli::cli_data::{EnvAction, InitShell, Operation};
 use database::db::{get_db_path, open, open_for_write};
 use database::history_loader::compact;
 use database::persistence::{
     ensure_config_directory, ensure_data_directory, get_default_alias_file_path,
     load_config, save_config, AppConfig,
 };
-use ops::apply::{apply_add, apply_change, apply_remove, ApplyOutcome};
+use ops::apply::{apply_add_template, apply_change, apply_edit, apply_remove, ApplyOutcome};
 use ops::alias_suggestions::is_system_command;
 use ops::delete_suggestion::delete_suggestion;
 use ops::get_suggestions;
 use ops::insert_command::insert_command;
+use ops::render_aliases::print_source_message;
 use shell::{render_shell_init, ShellOpts};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -48,20 +51,6 @@ fn to_absolute_path(path: &str) -> String {
     }
 }
 
-fn print_source_message() {
-    let shell_path = std::env::var("SHELL").unwrap_or_default();
-    let shell_file = if shell_path.contains("zsh") {
-        "~/.zshrc"
-    } else if shell_path.contains("bash") {
-        "~/.bashrc"
-    } else if shell_path.contains("fish") {
-        "~/.config/fish/config.fish"
-    } else {
-        "your shell's config file"
-    };
-    println!("\nTo use your new aliases immediately, run: \x1b[32msource {}\x1b[0m", shell_file);
-}
-
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
@@ -95,6 +84,8 @@ fn main() {
     }
 
     let config = load_config();
+    let reminders = config.as_ref().is_none_or(|c| c.reminders);
+    let hooks = config.as_ref().is_none_or(|c| c.hooks);
     let mut alias_file_paths = config
         .as_ref()
         .map(|c| c.alias_file_paths.clone())
@@ -103,18 +94,25 @@ fn main() {
     // Fast path: `custom` uses the lightweight write-only open (no UDF, no migration).
     if args.len() > 1 && args[1] == "custom" {
         if args.len() < 3 {
-            eprintln!("Usage: {} custom <command> [--cwd <dir>] [--session <id>]", args[0]);
+            eprintln!(
+                "Usage: {} custom <command> [--cwd <dir>] [--session <id>] [--exit-code <n>] [--duration-ms <ms>]",
+                args[0]
+            );
             return;
         }
 
         let mut session_id: Option<String> = None;
         let mut cwd: Option<String> = None;
+        let mut exit_code: Option<i32> = None;
+        let mut duration_ms: Option<i64> = None;
         let mut cmd_parts: Vec<&str> = Vec::new();
         let mut i = 2;
         while i < args.len() {
             match args[i].as_str() {
                 "--session" if i + 1 < args.len() => { session_id = Some(args[i + 1].clone()); i += 2; }
                 "--cwd" if i + 1 < args.len() => { cwd = Some(args[i + 1].clone()); i += 2; }
+                "--exit-code" if i + 1 < args.len() => { exit_code = args[i + 1].parse().ok(); i += 2; }
+                "--duration-ms" if i + 1 < args.len() => { duration_ms = args[i + 1].parse().ok(); i += 2; }
                 _ => { cmd_parts.push(&args[i]); i += 1; }
             }
         }
@@ -125,7 +123,35 @@ fn main() {
             Ok(c) => c,
             Err(e) => { eprintln!("alman: DB error: {e}"); return; }
         };
-        insert_command(command, &conn, session_id.as_deref(), cwd.as_deref());
+        insert_command(
+            command,
+            &conn,
+            session_id.as_deref(),
+            cwd.as_deref(),
+            exit_code,
+            duration_ms,
+        );
+        return;
+    }
+
+    // Fast path: `remind` runs before every command from the shell hooks.
+    if args.len() > 2 && args[1] == "remind" {
+        if !reminders {
+            return;
+        }
+        let db_path = get_db_path();
+        let now = database::db::now_secs();
+        let due = match database::db::open_read_only(&db_path) {
+            Ok(conn) => ops::remind::due_reminder(&conn, &args[2..].join(" "), now),
+            Err(_) => return,
+        };
+        // A write connection only when there is something to show.
+        if let Ok(Some(due)) = due {
+            if let Ok(conn) = open_for_write(&db_path) {
+                let _ = ops::remind::mark_shown(&conn, &due.definition, now);
+            }
+            eprintln!("{}", due.hint.yellow());
+        }
         return;
     }
 
@@ -143,7 +169,7 @@ fn main() {
                 let new_default = alias_file_paths.remove(pos);
                 alias_file_paths.insert(0, new_default);
             }
-            let _ = save_config(&AppConfig { alias_file_paths });
+            let _ = save_config(&AppConfig { alias_file_paths, reminders, hooks });
             println!("Default alias file path set to {}", cli_path_str.green());
         } else {
             // No subcommand and no path flag → launch TUI.
@@ -153,6 +179,7 @@ fn main() {
                 Err(e) => { eprintln!("alman: DB error: {e}"); return; }
             };
             crate::database::db::migrate_aliases_if_needed(&conn, &alias_file_paths);
+            ops::sync_history::sync_unless_hooked(&conn, hooks);
             if let Err(e) = run_tui(alias_file_paths, conn) {
                 eprintln!("{}", format!("TUI error: {}", e).red());
             }
@@ -165,7 +192,7 @@ fn main() {
         let cli_path_str = to_absolute_path(&cli_path.to_string_lossy());
         if !alias_file_paths.contains(&cli_path_str) {
             alias_file_paths.push(cli_path_str);
-            let _ = save_config(&AppConfig { alias_file_paths: alias_file_paths.clone() });
+            let _ = save_config(&AppConfig { alias_file_paths: alias_file_paths.clone(), reminders, hooks });
         }
     }
 
@@ -183,45 +210,117 @@ fn main() {
     match cli.operation.as_ref().unwrap() {
         Operation::Add { alias, command } => {
             let Some(conn) = open_conn() else { return; };
-            match apply_add(&conn, alias, command) {
-                Ok(_) => print_source_message(),
+            match apply_add_template(&conn, alias, command) {
+                Ok(_) => print_source_message(&conn),
                 Err(e) => eprintln!("{}", format!("Error adding alias: {}", e).red()),
             }
         }
+        Operation::AddRaw { name, posix, bash, zsh, fish, nu, pwsh, tcsh } => {
+            let Some(conn) = open_conn() else { return; };
+            let mut bodies = registry::RawBodies::default();
+            for (dialect, body) in [("posix", posix), ("bash", bash), ("zsh", zsh), ("fish", fish), ("nu", nu), ("pwsh", pwsh), ("tcsh", tcsh)] {
+                let Some(body) = body else { continue };
+                let body = match body.strip_prefix('@') {
+                    Some(path) => match fs::read_to_string(path) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            eprintln!("{}", format!("Error reading {}: {}", path, e).red());
+                            return;
+                        }
+                    },
+                    None => body.clone(),
+                };
+                bodies.0.insert(dialect.to_string(), body);
+            }
+            match ops::apply::apply_add_raw(&conn, name, bodies) {
+                Ok(_) => print_source_message(&conn),
+                Err(e) => eprintln!("{}", format!("Error adding function: {}", e).red()),
+            }
+        }
+        Operation::Env { action } => {
+            let Some(conn) = open_conn() else { return; };
+            match action {
+                EnvAction::Set { name, value } => match ops::apply::apply_env_set(&conn, name, value) {
+                    Ok(_) => print_source_message(&conn),
+                    Err(e) => eprintln!("{}", format!("Error setting variable: {}", e).red()),
+                },
+                EnvAction::Unset { name } => match ops::apply::apply_env_unset(&conn, name) {
+                    Ok(ApplyOutcome::NotFound { name }) => {
+                        eprintln!("{}", format!("Variable '{}' not found.", name).red());
+                    }
+                    Ok(_) => print_source_message(&conn),
+                    Err(e) => eprintln!("{}", format!("Error unsetting variable: {}", e).red()),
+                },
+                EnvAction::List => {
+                    let defs = registry::list_definitions(&conn).unwrap_or_default();
+                    let vars: Vec<(&str, &str)> = defs
+                        .iter()
+                        .filter_map(|d| match &d.kind {
+                            registry::DefinitionKind::Env(value) => Some((d.name.as_str(), value.as_str())),
+                            _ => None,
+                        })
+                        .collect();
+                    if vars.is_empty() {
+                        println!("{}", "No variables found.".yellow());
+                        return;
+                    }
+                    for (name, value) in &vars {
+                        println!("{}={}", name.cyan(), value);
+                    }
+                }
+            }
+        }
         Operation::Remove { alias } => {
             let Some(conn) = open_conn() else { return; };
             match apply_remove(&conn, alias) {
                 Ok(ApplyOutcome::NotFound { name }) => {
                     eprintln!("{}", format!("Alias '{}' not found.", name).red());
                 }
-                Ok(_) => print_source_message(),
+                Ok(_) => print_source_message(&conn),
                 Err(e) => eprintln!("{}", format!("Error removing alias: {}", e).red()),
             }
         }
         Operation::List => {
             let Some(conn) = open_conn() else { return; };
             let defs = registry::list_definitions(&conn).unwrap_or_default();
+            let defs: Vec<_> = defs.into_iter().filter(|d| !d.kind.is_env()).collect();
             let renderer = render::PosixRenderer;
             use render::ShellRenderer;
-            let aliases: Vec<(String, String)> = defs
+            let usage = registry::definition_usage(&conn).unwrap_or_default();
+            let aliases: Vec<(String, String, String, String)> = defs
                 .iter()
-                .map(|d| (d.name.clone(), renderer.render_template_body(&d.template)))
+                .map(|d| {
+                    let (uses, last) = match usage.get(&d.name) {
+                        Some((uses, last)) => {
+                            let day = chrono::DateTime::from_timestamp(*last, 0)
+                                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                                .unwrap_or_default();
+                            (uses.to_string(), day)
+                        }
+                        None => ("0".to_string(), "never".to_string()),
+                    };
+                    (d.name.clone(), renderer.render_summary(d), uses, last)
+                })
                 .collect();
             if aliases.is_empty() {
                 println!("{}", "No aliases found.".yellow());
                 return;
             }
-            let max_alias_length = aliases.iter().map(|(a, _)| a.len()).max().unwrap_or(5).max(5);
-            let max_command_length = aliases.iter().map(|(_, c)| c.len()).max().unwrap_or(7).max(7);
-            println!("{}", format!("┌{:─<alias$}┬{:─<cmd$}┐", "", "", alias = max_alias_length + 2, cmd = max_command_length + 2).cyan());
-            println!("{}", format!("│ {:<alias$} │ {:<cmd$} │", "ALIAS", "COMMAND", alias = max_alias_length, cmd = max_command_length).cyan());
-            println!("{}", format!("├{:─<alias$}┼{:─<cmd$}┤", "", "", alias = max_alias_length + 2, cmd = max_command_length + 2).cyan());
-            for (alias, command) in &aliases {
+            let max_alias_length = aliases.iter().map(|(a, ..)| a.len()).max().unwrap_or(5).max(5);
+            let max_command_length = aliases.iter().map(|(_, c, ..)| c.len()).max().unwrap_or(7).max(7);
+            let max_uses_length = aliases.iter().map(|(_, _, u, _)| u.len()).max().unwrap_or(4).max(4);
+            let max_last_length = aliases.iter().map(|(.., l)| l.len()).max().unwrap_or(9).max(9);
+            println!("{}", format!("┌{:─<alias$}┬{:─<cmd$}┬{:─<uses$}┬{:─<last$}┐", "", "", "", "", alias = max_alias_length + 2, cmd = max_command_length + 2, uses = max_uses_length + 2, last = max_last_length + 2).cyan());
+            println!("{}", format!("│ {:<alias$} │ {:<cmd$} │ {:>uses$} │ {:<last$} │", "ALIAS", "COMMAND", "USES", "LAST USED", alias = max_alias_length, cmd = max_command_length, uses = max_uses_length, last = max_last_length).cyan());
+            println!("{}", format!("├{:─<alias$}┼{:─<cmd$}┼{:─<uses$}┼{:─<last$}┤", "", "", "", "", alias = max_alias_length + 2, cmd = max_command_length + 2, uses = max_uses_length + 2, last = max_last_length + 2).cyan());
+            for (alias, command, uses, last) in &aliases {
                 let alias_cell = format!("{:<width$}", alias, width = max_alias_length).cyan();
                 let cmd_cell = format!("{:<width$}", command, width = max_command_length);
-                println!("│ {} │ {} │", alias_cell, cmd_cell);
+                let uses_cell = format!("{:>width$}", uses, width = max_uses_length).yellow();
+                let last_cell = format!("{:<width$}", last, width = max_last_length);
+                println!("│ {} │ {} │ {} │ {} │", alias_cell, cmd_cell, uses_cell, last_cell);
             }
-            println!("{}", format!("└{:─<alias$}┴{:─<cmd$}┘", "", "", alias = max_alias_length + 2, cmd = max_command_length + 2).cyan());
+            println!("{}", format!("└{:─<alias$}┴{:─<cmd$}┴{:─<uses$}┴{:─<last$}┘", "", "", "", "", alias = max_alias_length + 2, cmd = max_command_length + 2, uses = max_uses_length + 2, last = max_last_length + 2).cyan());
             println!("{}", format!("Total: {} alias(es)", aliases.len()).green());
         }
         Operation::Change { old_alias, new_alias } => {
@@ -230,21 +329,70 @@ fn main() {
                 Ok(ApplyOutcome::NotFound { name }) => {
                     eprintln!("{}", format!("Alias '{}' not found.", name).red());
                 }
-                Ok(_) => print_source_message(),
+                Ok(_) => print_source_message(&conn),
                 Err(e) => eprintln!("{}", format!("Error changing alias: {}", e).red()),
             }
         }
-        Operation::GetSuggestions { num } => {
+        Operation::Edit { name, command, template, rename } => {
             let Some(conn) = open_conn() else { return; };
+            let editor_mode = command.is_none() && template.is_none() && rename.is_none();
+            let new_template = if editor_mode {
+                match registry::find_definition(&conn, name) {
+                    Ok(Some(def)) => match ops::apply::ensure_editable(&def, true) {
+                        Ok(()) => ops::edit::edit_in_editor(&def),
+                        Err(e) => {
+                            eprintln!("{}", format!("Error editing alias: {}", e).red());
+                            return;
+                        }
+                    },
+                    Ok(None) => {
+                        eprintln!("{}", format!("Alias '{}' not found.", name).red());
+                        return;
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                ops::edit::template_from_args(command.as_deref(), template.as_deref())
+            };
+            let new_template = match new_template {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", format!("Error editing alias: {}", e).red());
+                    return;
+                }
+            };
+            if new_template.is_none() && rename.is_none() {
+                println!("{}", "No changes.".yellow());
+                return;
+            }
+            match apply_edit(&conn, name, new_template.as_ref(), rename.as_deref()) {
+                Ok(ApplyOutcome::NotFound { name }) => {
+                    eprintln!("{}", format!("Alias '{}' not found.", name).red());
+                }
+                Ok(_) => print_source_message(&conn),
+                Err(e) => eprintln!("{}", format!("Error editing alias: {}", e).red()),
+            }
+        }
+        Operation::GetSuggestions { num, here, dir } => {
+            let Some(conn) = open_conn() else { return; };
+            ops::sync_history::sync_unless_hooked(&conn, hooks);
             if let Some(n) = num {
                 if *n == 0 {
                     eprintln!("{}", "Number of suggestions must be greater than 0.".red());
                     return;
                 }
             }
+            let scope = match dir {
+                Some(d) => Some(get_suggestions::resolve_scope(Path::new(&to_absolute_path(&d.to_string_lossy())))),
+                None if *here => get_suggestions::current_scope(),
+                None => None,
+            };
+            if let Some(ref s) = scope {
+                println!("Ranking for {}", s.display().to_string().green());
+            }
             let default_path = get_default_alias_file_path();
             let alias_path = alias_file_paths.first().unwrap_or(&default_path);
-            let list = get_suggestions::get_suggestions_with_aliases(*num, &conn, alias_path);
+            let list = get_suggestions::get_suggestions_with_aliases(*num, &conn, alias_path, scope.as_deref());
 
             if list.is_empty() {
                 println!("{}", "No suggestions found.".yellow());
@@ -252,23 +400,34 @@ fn main() {
             }
 
             let filtered: Vec<_> = list.iter().map(|cmd| {
-                let top_alias = cmd.alias_suggestions.iter().find(|a| !is_system_command(&a.alias));
-                (cmd, top_alias)
+                let mut usable = cmd.alias_suggestions.iter().filter(|a| !is_system_command(&a.alias));
+                let top_alias = usable.next();
+                // Commands run under sudo also show the wrapper-keeping variant.
+                let wrapped = cmd.command.wrapper.as_ref().and_then(|w| {
+                    let full = format!("{w} {}", cmd.command.command_text);
+                    usable.find(|a| a.command == full)
+                });
+                (cmd, top_alias, wrapped)
             }).collect();
 
-            let max_command_length = filtered.iter().map(|(c, _)| c.command.command_text.len()).max().unwrap_or(7).max(7);
-            let max_alias_length   = filtered.iter().map(|(_, a)| a.map(|x| x.alias.len()).unwrap_or(0)).max().unwrap_or(9).max(9);
-            let max_score_length   = filtered.iter().map(|(c, _)| format!("{:.0}", c.command.score).len()).max().unwrap_or(5).max(5);
+            let alias_cell = |top: Option<&ops::alias_suggestions::AliasSuggestion>, wrapped: Option<&ops::alias_suggestions::AliasSuggestion>| {
+                match (top, wrapped) {
+                    (Some(a), Some(w)) => format!("{}, {}", a.alias, w.alias),
+                    (Some(a), None) => a.alias.clone(),
+                    _ => String::new(),
+                }
+            };
+            let max_command_length = filtered.iter().map(|(c, _, _)| c.command.command_text.len()).max().unwrap_or(7).max(7);
+            let max_alias_length   = filtered.iter().map(|(_, a, w)| alias_cell(*a, *w).len()).max().unwrap_or(9).max(9);
+            let max_score_length   = filtered.iter().map(|(c, _, _)| format!("{:.0}", c.command.score).len()).max().unwrap_or(5).max(5);
 
             println!("{}", format!("┌{:─<cmd$}┬{:─<alias$}┬{:─<score$}┐", "", "", "", cmd = max_command_length + 2, alias = max_alias_length + 2, score = max_score_length + 2).cyan());
             println!("{}", format!("│ {:<cmd$} │ {:>alias$} │ {:>score$} │", "COMMAND", "TOP ALIAS", "SCORE", cmd = max_command_length, alias = max_alias_length, score = max_score_length).cyan());
             println!("{}", format!("├{:─<cmd$}┼{:─<alias$}┼{:─<score$}┤", "", "", "", cmd = max_command_length + 2, alias = max_alias_length + 2, score = max_score_length + 2).cyan());
 
-            for (cmd_with_alias, top_alias_opt) in &filtered {
+            for (cmd_with_alias, top_alias_opt, wrapped_opt) in &filtered {
                 let command_text = format!("{:<width$}", cmd_with_alias.command.command_text, width = max_command_length);
-                let alias_text = top_alias_opt
-                    .map(|a| format!("{:>width$}", a.alias, width = max_alias_length))
-                    .unwrap_or_else(|| format!("{:>width$}", "", width = max_alias_length));
+                let alias_text = format!("{:>width$}", alias_cell(*top_alias_opt, *wrapped_opt), width = max_alias_length);
                 let score_text = format!("{:>width$}", format!("{:.0}", cmd_with_alias.command.score), width = max_score_length);
                 println!("│ {} │ {} │ {} │",
                     command_text.bold(),
@@ -285,14 +444,68 @@ fn main() {
             delete_suggestion(alias, &conn);
             println!("{}", format!("Deleted suggestions for: {}", alias).yellow());
         }
+        Operation::WhichDef { json, command } => {
+            let Some(conn) = open_conn() else { return; };
+            let command = command.join(" ");
+            let defs = registry::list_definitions(&conn).unwrap_or_default();
+            let matches = ops::lookup::matching_definitions(&defs, &command);
+            if *json {
+                println!("{}", ops::lookup::matches_to_json(&matches));
+                return;
+            }
+            if matches.is_empty() {
+                println!("{}", "No alias or function produces this command.".yellow());
+                return;
+            }
+            for m in &matches {
+                let bindings: Vec<String> = m
+                    .binding
+                    .slots
+                    .iter()
+                    .map(|(n, v)| format!("{}={}", m.def.template.slot_name(*n), v))
+                    .collect();
+                let bindings = if bindings.is_empty() { String::new() } else { format!("  ({})", bindings.join(", ")) };
+                println!("{} {}{}", m.def.kind.as_str().dimmed(), m.invocation().cyan().bold(), bindings);
+            }
+        }
+        Operation::SyncHistory { files } => {
+            let Some(conn) = open_conn() else { return; };
+            let default = crate::database::history_loader::get_history_file_path().map(PathBuf::from);
+            let files = if files.is_empty() {
+                match &default {
+                    Ok(path) => vec![path.clone()],
+                    Err(e) => { eprintln!("{}", format!("{}", e).red()); return; }
+                }
+            } else {
+                files.clone()
+            };
+            for file in &files {
+                match ops::sync_history::sync_history_file(&conn, file, default.as_deref().ok()) {
+                    Ok(n) => println!("Recorded {} new command(s) from {}", n, file.display()),
+                    Err(e) => eprintln!("{}", format!("Error reading {}: {}", file.display(), e).red()),
+                }
+            }
+        }
         Operation::Tui => {
             let Some(conn) = open_conn() else { return; };
+            ops::sync_history::sync_unless_hooked(&conn, hooks);
             if let Err(e) = run_tui(alias_file_paths, conn) {
                 eprintln!("{}", format!("TUI error: {}", e).red());
             }
         }
-        Operation::Init { shell } => {
-            let opts = ShellOpts::new();
+        Operation::Init { shell: InitShell::Nu, .. } => {
+            let Some(conn) = open_conn() else { return; };
+            match ops::render_aliases::write_nu_init(&conn) {
+                Ok(path) => println!(
+                    "Wrote {}. Add this line to your config.nu ($nu.config-path):\n  source {}",
+                    path.display(),
+                    path.display()
+                ),
+                Err(e) => eprintln!("{}", format!("Error writing the nushell init file: {}", e).red()),
+            }
+        }
+        Operation::Init { shell, hook } => {
+            let opts = ShellOpts { bash_hook: *hook, ..ShellOpts::new() };
             println!("{}", render_shell_init(shell.clone(), &opts));
         }
         Operation::InitData => {
@@ -305,9 +518,7 @@ fn main() {
                 return;
             }
             if load_config().is_none() {
-                let _ = save_config(&AppConfig {
-                    alias_file_paths: vec![get_default_alias_file_path()],
-                });
+                let _ = save_config(&AppConfig::default());
             }
             // Opening the DB creates the schema, runs migration, and seeds from history if new.
             let conn = match open(&get_db_path()) {
@@ -327,22 +538,19 @@ fn main() {
                 let _ = fs::write(&default_alias_path, "# Alman aliases file\n");
             }
         }
-        Operation::ListAliasFiles => {
+        Operation::ListAliasFiles | Operation::Remind { .. } => {
             // Handled in the fast-path above; unreachable here.
         }
         Operation::RenderAliases { shell } => {
             let Some(conn) = open_conn() else { return; };
-            let renderer = crate::defaults::renderer_for(shell);
-            let defs = crate::registry::list_definitions(&conn).unwrap_or_default();
-            for def in &defs {
-                println!("{}", renderer.render_definition(def));
-            }
+            print!("{}", ops::render_aliases::render_aliases(&conn, shell));
         }
         Operation::ExportAliases => {
             let Some(conn) = open_conn() else { return; };
             let renderer = crate::render::PosixRenderer;
             use crate::render::ShellRenderer;
-            let defs = crate::registry::list_definitions(&conn).unwrap_or_default();
+            let mut defs = crate::registry::list_definitions(&conn).unwrap_or_default();
+            defs.sort_by_key(|d| !d.kind.is_env());
             for def in &defs {
                 println!("{}", renderer.render_definition(def));
             }
@@ -350,28 +558,10 @@ fn main() {
         Operation::GetTemplates { num } => {
             let Some(conn) = open_conn() else { return; };
             let limit = num.unwrap_or(10);
-            let tokenizer = crate::defaults::default_tokenizer();
-            let events: Vec<String> = {
-                let mut stmt = match conn
-                    .prepare("SELECT command FROM events ORDER BY ts DESC LIMIT 2000")
-                {
-                    Ok(s) => s,
-                    Err(e) => { eprintln!("{e}"); return; }
-                };
-                let rows = stmt.query_map([], |r| r.get(0));
-                let collected = match rows {
-                    Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
-                    Err(e) => { eprintln!("{e}"); return; }
-                };
-                collected
+            let templates = match crate::ops::get_templates::mine_recent_templates(&conn) {
+                Ok(t) => t,
+                Err(e) => { eprintln!("{e}"); return; }
             };
-            let tokenized: Vec<Vec<String>> = events
-                .iter()
-                .map(|e| tokenizer.tokenize(e))
-                .filter(|t| !t.is_empty())
-                .collect();
-            let miner = crate::defaults::default_miner();
-            let templates = miner.mine(&tokenized);
             let renderer = crate::render::PosixRenderer;
             use crate::registry::DefinitionKind;
             use crate::render::ShellRenderer;
@@ -380,11 +570,7 @@ fn main() {
             println!("{}", format!("│ {:<38} │ {:<58} │ {:>6} │ {:>6} │", "KIND/NAME", "DEFINITION", "SUPPORT", "SCORE").cyan());
             println!("{}", format!("├{:─<40}┼{:─<60}┼{:─<8}┼{:─<8}┤", "", "", "", "").cyan());
             for mt in templates.iter().take(limit) {
-                let kind = if mt.template.is_zero_slot() || mt.template.only_trailing_single_slot() {
-                    DefinitionKind::Alias
-                } else {
-                    DefinitionKind::Function
-                };
+                let kind = DefinitionKind::for_template(&mt.template);
                 let name = mt
                     .template
                     .parts
@@ -413,6 +599,8 @@ fn main() {
                         match &kind {
                             DefinitionKind::Alias => "alias",
                             DefinitionKind::Function => "fn",
+                            DefinitionKind::Raw(_) => "raw",
+                            DefinitionKind::Env(_) => "env",
                         },
                         name
                     ),
@@ -1,19 +1,139 @@
 use crate::mining::scorer::{TemplateScorer, TemplateStats};
+use crate::ast::{ShellOp, Token};
 use crate::template::{CommandTemplate, TemplatePart};
+use crate::tokenize::{split_flags, Arg};
+use std::collections::{HashMap, HashSet};
 
 const MIN_SUPPORT: usize = 3;
 const MIN_DISTINCT_SLOT_VALUES: usize = 2;
 const MAX_SLOTS: usize = 2;
 const MAX_TEMPLATE_TOKENS: usize = 8;
+/// Name length assumed when scoring, before the user has picked a name.
+const ASSUMED_NAME_LEN: usize = 4;
 
 pub struct MinedTemplate {
     pub template: CommandTemplate,
     pub stats: TemplateStats,
     pub score: f64,
+    /// Distinct values each slot took and how many rows had each, most frequent
+    /// first (index 0 is slot 1).
+    pub slot_values: Vec<Vec<(String, u32)>>,
+}
+
+/// One recorded command, tokenized, with the session context sequence mining needs.
+#[derive(Debug, Clone, Default)]
+pub struct TokenizedEvent {
+    pub tokens: Vec<Token>,
+    pub session_id: Option<String>,
+    pub ts: i64,
 }
 
 pub trait TemplateMiner: Send + Sync {
-    fn mine(&self, tokenized_events: &[Vec<String>]) -> Vec<MinedTemplate>;
+    fn mine(&self, events: &[TokenizedEvent]) -> Vec<MinedTemplate>;
+}
+
+/// Runs several miners over the same events and merges their results by score.
+pub struct CompositeMiner {
+    miners: Vec<Box<dyn TemplateMiner>>,
+}
+
+impl CompositeMiner {
+    pub fn new(miners: Vec<Box<dyn TemplateMiner>>) -> Self {
+        Self { miners }
+    }
+}
+
+impl TemplateMiner for CompositeMiner {
+    fn mine(&self, events: &[TokenizedEvent]) -> Vec<MinedTemplate> {
+        let mut results: Vec<MinedTemplate> =
+            self.miners.iter().flat_map(|m| m.mine(events)).collect();
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results
+    }
+}
+
+/// Generalize equal-length token rows column by column: constant columns become
+/// literals (or operators), varying flag-free columns become slots numbered after
+/// the ones already in `slot_values` (which receives each new slot's distinct values).
+///
+/// Returns `None` when a column varies over flags or operators, or the slot
+/// budget is exceeded.
+pub(crate) fn generalize_rows(rows: &[&[Token]], slot_values: &mut Vec<Vec<(String, u32)>>) -> Option<Vec<TemplatePart>> {
+    let len = rows.first()?.len();
+    (0..len)
+        .map(|pos| {
+            if let Token::Op(_) = &rows[0][pos] {
+                return rows
+                    .iter()
+                    .all(|r| r[pos] == rows[0][pos])
+                    .then(|| TemplatePart::from_token(&rows[0][pos]));
+            }
+            let values: Vec<&str> = rows.iter().map(|r| r[pos].as_word()).collect::<Option<_>>()?;
+            generalize_column(&values, pos == 0, slot_values)
+        })
+        .collect()
+}
+
+/// Generalize one column of values: a literal when constant, otherwise a new slot.
+/// The command word (`is_command`) and columns mixing in flags never become slots.
+fn generalize_column(values: &[&str], is_command: bool, slot_values: &mut Vec<Vec<(String, u32)>>) -> Option<TemplatePart> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for v in values {
+        *counts.entry(v).or_insert(0) += 1;
+    }
+
+    if counts.len() == 1 {
+        // Constant — literal
+        return Some(TemplatePart::Literal(values[0].to_string()));
+    }
+
+    let any_flag = counts.keys().any(|v| v.starts_with('-'));
+    if is_command || counts.len() < MIN_DISTINCT_SLOT_VALUES || any_flag {
+        // The command itself varies, or flags are mixed in — can't template cleanly
+        return None;
+    }
+    if slot_values.len() >= MAX_SLOTS {
+        return None;
+    }
+    let mut distinct: Vec<(&str, usize)> = counts.into_iter().collect();
+    distinct.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    slot_values.push(distinct.into_iter().map(|(v, n)| (v.to_string(), n as u32)).collect());
+    Some(TemplatePart::Slot(slot_values.len() as u32))
+}
+
+/// Score `template`, seen `support` times, and package it with its slots' values.
+pub(crate) fn mined_template(
+    scorer: &dyn TemplateScorer,
+    template: CommandTemplate,
+    support: usize,
+    distinct_per_slot: Vec<u32>,
+    slot_values: Vec<Vec<(String, u32)>>,
+) -> MinedTemplate {
+    let stats = TemplateStats {
+        support: support as u32,
+        distinct_per_slot,
+        literal_len: literal_len(&template),
+        name_len: ASSUMED_NAME_LEN,
+    };
+    let score = scorer.score(&stats);
+    MinedTemplate { template, stats, score, slot_values }
+}
+
+/// Distinct-value counts per slot, as `TemplateStats` wants them.
+pub(crate) fn distinct_counts(slot_values: &[Vec<(String, u32)>]) -> Vec<u32> {
+    slot_values.iter().map(|v| v.len() as u32).collect()
+}
+
+/// Total characters in the literal parts of a template.
+pub(crate) fn literal_len(template: &CommandTemplate) -> usize {
+    template
+        .parts
+        .iter()
+        .map(|p| match p {
+            TemplatePart::Literal(s) => s.len(),
+            TemplatePart::Slot(_) | TemplatePart::AndThen | TemplatePart::Rest | TemplatePart::Op(_) => 0,
+        })
+        .sum()
 }
 
 pub struct FixedArityMiner {
@@ -26,99 +146,146 @@ impl FixedArityMiner {
     }
 }
 
+/// A flag as it appears in a command: its text, whether a value follows it, and
+/// which repetition it is (`-v a -v b`).
+type FlagKey<'a> = (&'a str, bool, usize);
+
+/// A command line split into positional words, flags and operators, keeping
+/// the original order.
+struct ParsedRow<'a> {
+    positionals: Vec<&'a str>,
+    /// Whether each positional is the command word of a simple command.
+    is_command: Vec<bool>,
+    flags: HashMap<FlagKey<'a>, Option<&'a str>>,
+    /// Positionals (`None`) and operators in order; rows only share a template
+    /// when their shapes match.
+    shape: Vec<Option<&'a ShellOp>>,
+    layout: Vec<Column<'a>>,
+}
+
+#[derive(Clone, Copy)]
+enum Column<'a> {
+    Positional(usize),
+    Flag(FlagKey<'a>),
+    Op(&'a ShellOp),
+}
+
+impl<'a> ParsedRow<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        let mut row = ParsedRow {
+            positionals: Vec::new(),
+            is_command: Vec::new(),
+            flags: HashMap::new(),
+            shape: Vec::new(),
+            layout: Vec::new(),
+        };
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        let mut expect_command = true;
+        for arg in split_flags(tokens) {
+            match arg {
+                Arg::Positional(word) => {
+                    row.layout.push(Column::Positional(row.positionals.len()));
+                    row.positionals.push(word);
+                    row.is_command.push(expect_command);
+                    row.shape.push(None);
+                    expect_command = false;
+                }
+                Arg::Op(op) => {
+                    row.layout.push(Column::Op(op));
+                    row.shape.push(Some(op));
+                    // A redirection target is not a command; after `|`, `&&`, `(` … one follows.
+                    if !matches!(op, ShellOp::Redirect(_)) {
+                        expect_command = *op != ShellOp::SubshellClose;
+                    }
+                }
+                Arg::Flag { flag, value } => {
+                    let nth = seen.entry(flag).or_insert(0);
+                    let key = (flag, value.is_some(), *nth);
+                    *nth += 1;
+                    row.flags.insert(key, value);
+                    row.layout.push(Column::Flag(key));
+                }
+            }
+        }
+        row
+    }
+}
+
 impl TemplateMiner for FixedArityMiner {
-    fn mine(&self, tokenized_events: &[Vec<String>]) -> Vec<MinedTemplate> {
-        use std::collections::HashMap;
-        // Bucket by (command[0], token_count)
-        let mut buckets: HashMap<(String, usize), Vec<Vec<String>>> = HashMap::new();
-        for tokens in tokenized_events {
-            if tokens.is_empty() {
+    /// Commands are bucketed by command word and shape: positional words and
+    /// operators in order. A flag stays literal while its value may become a
+    /// slot, and flags missing from some commands in a bucket are left out of
+    /// the template rather than splitting it.
+    fn mine(&self, events: &[TokenizedEvent]) -> Vec<MinedTemplate> {
+        // Bucket by (command[0], shape)
+        let mut buckets: HashMap<(&str, Vec<Option<&ShellOp>>), Vec<ParsedRow>> = HashMap::new();
+        for event in events {
+            let row = ParsedRow::new(&event.tokens);
+            let Some(command) = row.positionals.first().copied() else {
                 continue;
-            }
-            let key = (tokens[0].clone(), tokens.len());
-            buckets.entry(key).or_default().push(tokens.clone());
+            };
+            buckets.entry((command, row.shape.clone())).or_default().push(row);
         }
 
         let mut results = Vec::new();
-        let mut seen_templates: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut seen_templates: HashSet<String> = HashSet::new();
 
-        for ((cmd0, len), rows) in &buckets {
+        for ((_, shape), rows) in &buckets {
             if rows.len() < MIN_SUPPORT {
                 continue;
             }
-            if *len > MAX_TEMPLATE_TOKENS {
+            if shape.len() > MAX_TEMPLATE_TOKENS {
                 continue;
             }
 
-            // Per-position analysis
-            let mut parts: Vec<TemplatePart> = Vec::with_capacity(*len);
-            let mut slot_stats: Vec<u32> = Vec::new();
-            let mut slot_count = 0usize;
-            let mut reject = false;
-
-            for pos in 0..*len {
-                let values: std::collections::HashSet<&str> =
-                    rows.iter().map(|r| r[pos].as_str()).collect();
-                let distinct = values.len();
-
-                if pos == 0 {
-                    // Token 0 is always the command — always literal
-                    parts.push(TemplatePart::Literal(cmd0.clone()));
-                    continue;
-                }
-
-                let any_flag = values.iter().any(|v| v.starts_with('-'));
+            // Flags present in every row; the rest are optional and dropped.
+            let common: Vec<FlagKey> = rows[0]
+                .flags
+                .keys()
+                .filter(|key| rows.iter().all(|r| r.flags.contains_key(*key)))
+                .copied()
+                .collect();
 
-                if distinct == 1 {
-                    // Constant — literal
-                    parts.push(TemplatePart::Literal(rows[0][pos].clone()));
-                } else if distinct >= MIN_DISTINCT_SLOT_VALUES && !any_flag {
-                    // Variable, no flags → slot
-                    slot_count += 1;
-                    if slot_count > MAX_SLOTS {
-                        reject = true;
+            let mut slot_values: Vec<Vec<(String, u32)>> = Vec::new();
+            let mut parts: Vec<TemplatePart> = Vec::new();
+            let mut ok = true;
+            for column in &rows[0].layout {
+                let part = match *column {
+                    Column::Positional(i) => {
+                        let values: Vec<&str> = rows.iter().map(|r| r.positionals[i]).collect();
+                        generalize_column(&values, rows[0].is_command[i], &mut slot_values)
+                    }
+                    Column::Flag(key) if common.contains(&key) => {
+                        parts.push(TemplatePart::Literal(key.0.to_string()));
+                        if !key.1 {
+                            continue;
+                        }
+                        let values: Vec<&str> = rows.iter().filter_map(|r| r.flags[&key]).collect();
+                        generalize_column(&values, false, &mut slot_values)
+                    }
+                    Column::Flag(_) => continue,
+                    Column::Op(op) => Some(TemplatePart::from_token(&Token::Op(op.clone()))),
+                };
+                match part {
+                    Some(part) => parts.push(part),
+                    None => {
+                        ok = false;
                         break;
                     }
-                    let slot_idx = slot_count as u32;
-                    parts.push(TemplatePart::Slot(slot_idx));
-                    slot_stats.push(distinct as u32);
-                } else {
-                    // Varies but flags present, or partially flags — can't template cleanly
-                    reject = true;
-                    break;
                 }
             }
-
-            if reject || slot_count == 0 {
+            if !ok || slot_values.is_empty() || parts.len() > MAX_TEMPLATE_TOKENS {
                 continue;
             }
 
-            let template = CommandTemplate { parts };
+            let template = CommandTemplate::new(parts);
             let tmpl_key = template.to_json();
             if !seen_templates.insert(tmpl_key) {
                 continue;
             }
 
-            let literal_len: usize = template
-                .parts
-                .iter()
-                .map(|p| match p {
-                    TemplatePart::Literal(s) => s.len(),
-                    TemplatePart::Slot(_) => 0,
-                })
-                .sum();
-            let stats = TemplateStats {
-                support: rows.len() as u32,
-                distinct_per_slot: slot_stats,
-                literal_len,
-                name_len: 4, // placeholder; caller can refine
-            };
-            let score = self.scorer.score(&stats);
-            results.push(MinedTemplate {
-                template,
-                stats,
-                score,
-            });
+            let distinct = distinct_counts(&slot_values);
+            results.push(mined_template(&*self.scorer, template, rows.len(), distinct, slot_values));
         }
 
         results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
@@ -130,9 +297,13 @@ impl TemplateMiner for FixedArityMiner {
 mod tests {
     use super::*;
     use crate::mining::scorer::SavingsScorer;
+    use crate::tokenize::{ShellTokenizer, Tokenizer};
 
-    fn tok(s: &str) -> Vec<String> {
-        s.split_whitespace().map(|w| w.to_string()).collect()
+    fn tok(s: &str) -> TokenizedEvent {
+        TokenizedEvent {
+            tokens: ShellTokenizer.lex(s),
+            ..Default::default()
+        }
     }
 
     #[test]
@@ -150,5 +321,55 @@ mod tests {
         assert_eq!(t.parts.len(), 5);
         assert_eq!(t.parts[3], TemplatePart::Slot(1));
         assert_eq!(results[0].stats.support, 3);
+        let values = vec![("cache".to_string(), 1), ("db".to_string(), 1), ("web".to_string(), 1)];
+        assert_eq!(results[0].slot_values, vec![values]);
+    }
+
+    #[test]
+    fn flag_values_become_slots_and_optional_flags_are_dropped() {
+        let events = vec![
+            tok("kubectl logs -n prod pod-a"),
+            tok("kubectl logs -f -n staging pod-b"),
+            tok("kubectl logs -n dev pod-c --tail 50"),
+        ];
+        let results = FixedArityMiner::new(Box::new(SavingsScorer)).mine(&events);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].template.parts,
+            vec![
+                TemplatePart::Literal("kubectl".into()),
+                TemplatePart::Literal("logs".into()),
+                TemplatePart::Literal("-n".into()),
+                TemplatePart::Slot(1),
+                TemplatePart::Slot(2),
+            ]
+        );
+        assert_eq!(results[0].stats.support, 3);
+    }
+
+    #[test]
+    fn keeps_operators_and_never_slots_a_piped_command() {
+        let events = vec![
+            tok("ps aux | grep nginx > /tmp/ps.txt"),
+            tok("ps aux | grep redis > /tmp/ps.txt"),
+            tok("ps aux | grep 'web app' > /tmp/ps.txt"),
+            tok("ps aux | head > /tmp/ps.txt"),
+            tok("ps aux | tail > /tmp/ps.txt"),
+            tok("ps aux | less > /tmp/ps.txt"),
+        ];
+        let results = FixedArityMiner::new(Box::new(SavingsScorer)).mine(&events);
+        assert_eq!(results.len(), 1, "`ps aux | <cmd>` must not become a template");
+        assert_eq!(
+            results[0].template.parts,
+            vec![
+                TemplatePart::Literal("ps".into()),
+                TemplatePart::Literal("aux".into()),
+                TemplatePart::Op(ShellOp::Pipe),
+                TemplatePart::Literal("grep".into()),
+                TemplatePart::Slot(1),
+                TemplatePart::Op(ShellOp::Redirect(">".into())),
+                TemplatePart::Literal("/tmp/ps.txt".into()),
+            ]
+        );
     }
 }
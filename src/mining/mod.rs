@@ -1,2 +1,4 @@
 pub mod miner;
+pub mod prefix;
 pub mod scorer;
+pub mod sequence;
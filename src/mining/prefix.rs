@@ -0,0 +1,115 @@
+use crate::ast::Token;
+use crate::mining::miner::{mined_template, MinedTemplate, TemplateMiner, TokenizedEvent};
+use crate::mining::scorer::TemplateScorer;
+use crate::template::{CommandTemplate, TemplatePart};
+use std::collections::{HashMap, HashSet};
+
+const MIN_SUPPORT: usize = 3;
+/// A one-word prefix would just re-alias the command itself.
+const MIN_PREFIX_TOKENS: usize = 2;
+const MAX_PREFIX_TOKENS: usize = 4;
+const MIN_DISTINCT_ARITIES: usize = 2;
+const MIN_DISTINCT_RESTS: usize = 2;
+
+/// Finds a literal prefix shared by commands that differ in how many arguments
+/// follow it (`rg foo src`, `rg foo src tests`, `rg foo lib`) and proposes it as
+/// `[prefix…, Rest]`, which `FixedArityMiner` cannot express.
+pub struct PrefixMiner {
+    scorer: Box<dyn TemplateScorer>,
+}
+
+impl PrefixMiner {
+    pub fn new(scorer: Box<dyn TemplateScorer>) -> Self {
+        Self { scorer }
+    }
+}
+
+impl TemplateMiner for PrefixMiner {
+    fn mine(&self, events: &[TokenizedEvent]) -> Vec<MinedTemplate> {
+        // prefix -> the remaining tokens of every command starting with it
+        let mut groups: HashMap<&[Token], Vec<&[Token]>> = HashMap::new();
+        for event in events {
+            let tokens = event.tokens.as_slice();
+            // The rest stands for arguments of the last command, so it must be plain words.
+            let last_op = tokens.iter().rposition(|t| t.as_word().is_none());
+            let shortest = MIN_PREFIX_TOKENS.max(last_op.map_or(0, |i| i + 2));
+            let longest = MAX_PREFIX_TOKENS.min(tokens.len().saturating_sub(1));
+            for k in shortest..=longest {
+                groups.entry(&tokens[..k]).or_default().push(&tokens[k..]);
+            }
+        }
+
+        let candidates: Vec<(&[Token], usize, usize)> = groups
+            .iter()
+            .filter(|(_, rests)| rests.len() >= MIN_SUPPORT)
+            .filter_map(|(prefix, rests)| {
+                let arities: HashSet<usize> = rests.iter().map(|r| r.len()).collect();
+                let distinct: HashSet<&[Token]> = rests.iter().copied().collect();
+                (arities.len() >= MIN_DISTINCT_ARITIES && distinct.len() >= MIN_DISTINCT_RESTS)
+                    .then_some((*prefix, rests.len(), distinct.len()))
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for &(prefix, support, distinct) in &candidates {
+            // Prefer the longest prefix: a shorter one seen exactly as often is only
+            // ever followed by the same extra words.
+            let extended = candidates.iter().any(|&(other, other_support, _)| {
+                other.len() > prefix.len() && other.starts_with(prefix) && other_support == support
+            });
+            if extended {
+                continue;
+            }
+
+            let mut parts: Vec<TemplatePart> = prefix.iter().map(TemplatePart::from_token).collect();
+            parts.push(TemplatePart::Rest);
+            let template = CommandTemplate::new(parts);
+            results.push(mined_template(&*self.scorer, template, support, vec![distinct as u32], Vec::new()));
+        }
+
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mining::scorer::SavingsScorer;
+    use crate::tokenize::{ShellTokenizer, Tokenizer};
+
+    fn tok(s: &str) -> TokenizedEvent {
+        TokenizedEvent {
+            tokens: ShellTokenizer.lex(s),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mines_shared_prefix_across_arities() {
+        let events = vec![
+            tok("rg --hidden foo src"),
+            tok("rg --hidden foo src tests"),
+            tok("rg --hidden foo lib"),
+            tok("rg --hidden bar"),
+        ];
+        let results = PrefixMiner::new(Box::new(SavingsScorer)).mine(&events);
+        let templates: Vec<&CommandTemplate> = results.iter().map(|r| &r.template).collect();
+        let expected = CommandTemplate::new(vec![
+            TemplatePart::Literal("rg".into()),
+            TemplatePart::Literal("--hidden".into()),
+            TemplatePart::Literal("foo".into()),
+            TemplatePart::Rest,
+        ]);
+        assert!(templates.contains(&&expected), "got {templates:?}");
+        // `rg --hidden` has more support (4) than `rg --hidden foo` (3), so both stay.
+        assert!(templates.iter().any(|t| t.parts.len() == 3));
+    }
+
+    #[test]
+    fn skips_prefix_with_a_single_arity() {
+        let events = vec![tok("make test a"), tok("make test b"), tok("make test c")];
+        let results = PrefixMiner::new(Box::new(SavingsScorer)).mine(&events);
+        assert!(results.is_empty(), "fixed-arity commands belong to FixedArityMiner");
+    }
+}
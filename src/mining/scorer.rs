@@ -2,7 +2,7 @@ pub struct TemplateStats {
     pub support: u32,
     pub distinct_per_slot: Vec<u32>,
     pub literal_len: usize, // total chars in all Literal parts
-    pub name_len: usize,    // suggested name length (passed in from caller)
+    pub name_len: usize,    // length of the name it will be called by (assumed until one is picked)
 }
 
 pub trait TemplateScorer: Send + Sync {
@@ -0,0 +1,204 @@
+use crate::ast::Token;
+use crate::mining::miner::{distinct_counts, generalize_rows, mined_template, MinedTemplate, TemplateMiner, TokenizedEvent};
+use crate::mining::scorer::TemplateScorer;
+use crate::template::{CommandTemplate, TemplatePart};
+use std::collections::{HashMap, HashSet};
+
+const MIN_SEQUENCE_SUPPORT: usize = 3;
+const MIN_SEQUENCE_LEN: usize = 2;
+const MAX_SEQUENCE_LEN: usize = 3;
+/// Commands further apart than this are not considered "back to back".
+const MAX_GAP_SECS: i64 = 300;
+const MAX_STEP_TOKENS: usize = 8;
+
+/// Finds runs of 2–3 commands that repeatedly follow each other within one shell
+/// session (e.g. `git add -A` → `git commit -m …` → `git push`) and proposes them
+/// as a single `&&`-chained template.
+pub struct SequenceMiner {
+    scorer: Box<dyn TemplateScorer>,
+}
+
+impl SequenceMiner {
+    pub fn new(scorer: Box<dyn TemplateScorer>) -> Self {
+        Self { scorer }
+    }
+}
+
+/// Shape of one step: command, subcommand (if any) and token count.
+type StepKey = (String, String, usize);
+
+fn step_count(t: &CommandTemplate) -> usize {
+    t.parts.iter().filter(|p| **p == TemplatePart::AndThen).count() + 1
+}
+
+fn step_key(tokens: &[Token]) -> StepKey {
+    let text = |t: Option<&Token>| t.cloned().map(Token::into_text).unwrap_or_default();
+    (text(tokens.first()), text(tokens.get(1)), tokens.len())
+}
+
+impl TemplateMiner for SequenceMiner {
+    fn mine(&self, events: &[TokenizedEvent]) -> Vec<MinedTemplate> {
+        // Split into sessions, each ordered by time. Every event stays in, so one
+        // that cannot be a step still separates its neighbours.
+        let mut sessions: HashMap<&str, Vec<&TokenizedEvent>> = HashMap::new();
+        for event in events {
+            if let Some(session) = event.session_id.as_deref() {
+                sessions.entry(session).or_default().push(event);
+            }
+        }
+        let usable = |e: &TokenizedEvent| !e.tokens.is_empty() && e.tokens.len() <= MAX_STEP_TOKENS;
+
+        let mut windows: HashMap<Vec<StepKey>, Vec<Vec<&[Token]>>> = HashMap::new();
+        for session in sessions.values_mut() {
+            session.sort_by_key(|e| e.ts);
+            for len in MIN_SEQUENCE_LEN..=MAX_SEQUENCE_LEN {
+                for window in session.windows(len) {
+                    let back_to_back = window.iter().all(|e| usable(e))
+                        && window.windows(2).all(|pair| {
+                            pair[1].ts - pair[0].ts <= MAX_GAP_SECS && pair[0].tokens != pair[1].tokens
+                        });
+                    if !back_to_back {
+                        continue;
+                    }
+                    let key: Vec<StepKey> = window.iter().map(|e| step_key(&e.tokens)).collect();
+                    windows
+                        .entry(key)
+                        .or_default()
+                        .push(window.iter().map(|e| e.tokens.as_slice()).collect());
+                }
+            }
+        }
+
+        let mut results: Vec<MinedTemplate> = Vec::new();
+        for (key, occurrences) in &windows {
+            if occurrences.len() < MIN_SEQUENCE_SUPPORT {
+                continue;
+            }
+
+            let mut parts: Vec<TemplatePart> = Vec::new();
+            let mut slot_values: Vec<Vec<(String, u32)>> = Vec::new();
+            let mut ok = true;
+            for step in 0..key.len() {
+                let rows: Vec<&[Token]> = occurrences.iter().map(|o| o[step]).collect();
+                match generalize_rows(&rows, &mut slot_values) {
+                    Some(step_parts) => {
+                        if step > 0 {
+                            parts.push(TemplatePart::AndThen);
+                        }
+                        parts.extend(step_parts);
+                    }
+                    None => {
+                        ok = false;
+                        break;
+                    }
+                }
+            }
+            if !ok {
+                continue;
+            }
+
+            let template = CommandTemplate::new(parts);
+            let distinct = distinct_counts(&slot_values);
+            results.push(mined_template(&*self.scorer, template, occurrences.len(), distinct, slot_values));
+        }
+
+        // A pair that only ever occurs inside a longer sequence adds nothing.
+        let skeleton = |t: &CommandTemplate| -> Vec<Option<String>> {
+            t.parts
+                .iter()
+                .map(|p| match p {
+                    TemplatePart::Literal(s) => Some(s.clone()),
+                    TemplatePart::Slot(_) | TemplatePart::Rest => None,
+                    TemplatePart::AndThen => Some("&&".to_string()),
+                    TemplatePart::Op(op) => Some(op.as_str().to_string()),
+                })
+                .collect()
+        };
+        let longer: Vec<(Vec<Option<String>>, u32)> = results
+            .iter()
+            .filter(|r| step_count(&r.template) > MIN_SEQUENCE_LEN)
+            .map(|r| (skeleton(&r.template), r.stats.support))
+            .collect();
+        let subsumed: HashSet<usize> = results
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| {
+                let sk = skeleton(&r.template);
+                longer.iter().any(|(long, support)| {
+                    *support == r.stats.support
+                        && long.len() > sk.len()
+                        && long.windows(sk.len()).any(|w| w == sk.as_slice())
+                })
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let mut results: Vec<MinedTemplate> = results
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !subsumed.contains(i))
+            .map(|(_, r)| r)
+            .collect();
+
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mining::scorer::SavingsScorer;
+    use crate::tokenize::{ShellTokenizer, Tokenizer};
+
+    fn ev(session: &str, ts: i64, s: &str) -> TokenizedEvent {
+        TokenizedEvent {
+            tokens: ShellTokenizer.lex(s),
+            session_id: Some(session.to_string()),
+            ts,
+        }
+    }
+
+    #[test]
+    fn mines_add_commit_push_triple() {
+        let mut events = Vec::new();
+        for (i, msg) in ["fix", "docs", "wip"].iter().enumerate() {
+            let session = format!("s{i}");
+            events.push(ev(&session, 10, "git add -A"));
+            events.push(ev(&session, 20, &format!("git commit -m {msg}")));
+            events.push(ev(&session, 30, "git push"));
+        }
+        let results = SequenceMiner::new(Box::new(SavingsScorer)).mine(&events);
+        assert_eq!(results.len(), 1, "pairs inside the triple must be folded away");
+        let t = &results[0].template;
+        assert_eq!(t.parts.iter().filter(|p| **p == TemplatePart::AndThen).count(), 2);
+        assert!(t.parts.contains(&TemplatePart::Slot(1)));
+        assert_eq!(results[0].stats.support, 3);
+    }
+
+    #[test]
+    fn ignores_commands_far_apart_or_without_session() {
+        let mut events = Vec::new();
+        for i in 0..3 {
+            let session = format!("s{i}");
+            events.push(ev(&session, 0, "make build"));
+            events.push(ev(&session, MAX_GAP_SECS + 1, "make test"));
+            events.push(TokenizedEvent { session_id: None, ..ev("", 0, "cargo build") });
+            events.push(TokenizedEvent { session_id: None, ..ev("", 1, "cargo test") });
+        }
+        let results = SequenceMiner::new(Box::new(SavingsScorer)).mine(&events);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn a_skipped_command_still_separates_its_neighbours() {
+        let mut events = Vec::new();
+        for i in 0..3 {
+            let session = format!("s{i}");
+            events.push(ev(&session, 10, "make build"));
+            events.push(ev(&session, 20, "docker run --rm -it -v /src:/src -w /src alpine sh"));
+            events.push(ev(&session, 30, "make test"));
+        }
+        let results = SequenceMiner::new(Box::new(SavingsScorer)).mine(&events);
+        assert!(results.is_empty(), "{:?}", results.iter().map(|r| r.template.to_text()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::ast::{Command, CommandList};
+use crate::tokenize::quote_word;
+
+/// Wrappers that run the command with other privileges. They are kept as an
+/// attribute of the command so suggestions can offer a variant that keeps them.
+const ELEVATORS: &[&str] = &["sudo", "doas"];
+
+/// A command with its wrappers peeled off.
+#[derive(Debug, PartialEq)]
+pub struct Unwrapped {
+    /// The underlying command's words.
+    pub words: Vec<String>,
+    /// The privilege wrapper with its options, e.g. `sudo -u postgres`.
+    pub elevation: Option<String>,
+}
+
+/// Reduces commands to the program actually being run, so `sudo systemctl restart`,
+/// `time cargo build` and `FOO=1 make` are counted as `systemctl restart`,
+/// `cargo build` and `make`.
+pub trait Normalizer: Send + Sync {
+    /// Strip wrappers from the words of one simple command.
+    fn unwrap(&self, words: &[String]) -> Unwrapped;
+
+    /// Strip wrappers from every command in `list`, including inside subshells.
+    fn normalize(&self, list: &mut CommandList) {
+        for item in &mut list.items {
+            for command in &mut item.pipeline.commands {
+                match command {
+                    Command::Simple(simple) => simple.words = self.unwrap(&simple.words).words,
+                    Command::Subshell { body, .. } => self.normalize(body),
+                }
+            }
+        }
+    }
+}
+
+/// Strips leading `NAME=value` assignments and well-known wrapper commands
+/// (`sudo`, `doas`, `time`, `nice`, `env`, `command`, `builtin`, `exec`,
+/// `nohup`, `noglob`, `stdbuf`, `ionice`) together with their options.
+pub struct WrapperNormalizer;
+
+impl WrapperNormalizer {
+    /// The options of wrapper `name` that take a separate value, or `None` when
+    /// `name` is not a wrapper.
+    fn value_options(name: &str) -> Option<&'static [&'static str]> {
+        Some(match name {
+            "sudo" => &["-u", "-g", "-p", "-C", "-D", "-h", "-r", "-t", "-U", "-T", "-R"],
+            "doas" => &["-u", "-C"],
+            "time" => &["-f", "-o", "--format", "--output"],
+            "nice" => &["-n", "--adjustment"],
+            "env" => &["-u", "-C", "-S", "--unset", "--chdir", "--split-string"],
+            "stdbuf" => &["-i", "-o", "-e", "--input", "--output", "--error"],
+            "ionice" => &["-c", "-n", "-p", "-P", "-u", "--class", "--classdata"],
+            "command" | "builtin" | "exec" | "nohup" | "noglob" => &[],
+            _ => return None,
+        })
+    }
+}
+
+/// `NAME=value` with a valid shell variable name.
+fn is_assignment(word: &str) -> bool {
+    let Some((name, _)) = word.split_once('=') else {
+        return false;
+    };
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Normalizer for WrapperNormalizer {
+    fn unwrap(&self, words: &[String]) -> Unwrapped {
+        let unchanged = || Unwrapped { words: words.to_vec(), elevation: None };
+        let mut elevation = None;
+        let mut i = 0;
+        loop {
+            while words.get(i).is_some_and(|w| is_assignment(w)) {
+                i += 1;
+            }
+            let Some(word) = words.get(i) else { break };
+            let name = word.rsplit('/').next().unwrap_or(word);
+            let Some(value_options) = Self::value_options(name) else { break };
+
+            let start = i;
+            i += 1;
+            while let Some(opt) = words.get(i) {
+                if opt == "--" {
+                    i += 1;
+                    break;
+                }
+                if !opt.starts_with('-') || opt.len() == 1 {
+                    break;
+                }
+                // `command -v git` looks a command up instead of running it.
+                if name == "command" && (opt == "-v" || opt == "-V") {
+                    return unchanged();
+                }
+                i += if value_options.contains(&opt.as_str()) { 2 } else { 1 };
+            }
+            if ELEVATORS.contains(&name) && elevation.is_none() {
+                let end = i.min(words.len());
+                let wrapper: Vec<String> = words[start..end].iter().map(|w| quote_word(w)).collect();
+                elevation = Some(wrapper.join(" "));
+            }
+        }
+        // `sudo -i`, a bare `time` or `env`: the wrapper is the command.
+        if i >= words.len() {
+            return unchanged();
+        }
+        Unwrapped { words: words[i..].to_vec(), elevation }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::{ShellTokenizer, Tokenizer};
+
+    fn unwrap(cmd: &str) -> (String, Option<String>) {
+        let u = WrapperNormalizer.unwrap(&ShellTokenizer.tokenize(cmd));
+        (u.words.join(" "), u.elevation)
+    }
+
+    #[test]
+    fn strips_wrappers_and_keeps_elevation() {
+        assert_eq!(unwrap("sudo systemctl restart nginx"), ("systemctl restart nginx".into(), Some("sudo".into())));
+        assert_eq!(unwrap("time cargo build"), ("cargo build".into(), None));
+        assert_eq!(unwrap("FOO=1 BAR=2 make"), ("make".into(), None));
+        assert_eq!(
+            unwrap("sudo -u postgres env PGDATA=/db nice -n 5 psql"),
+            ("psql".into(), Some("sudo -u postgres".into()))
+        );
+        assert_eq!(unwrap("/usr/bin/time -f %e doas -- ls /root"), ("ls /root".into(), Some("doas --".into())));
+    }
+
+    #[test]
+    fn leaves_non_wrapping_uses_alone() {
+        assert_eq!(unwrap("sudo -i"), ("sudo -i".into(), None));
+        assert_eq!(unwrap("command -v git"), ("command -v git".into(), None));
+        assert_eq!(unwrap("FOO=1"), ("FOO=1".into(), None));
+        assert_eq!(unwrap("git log --format=x"), ("git log --format=x".into(), None));
+    }
+}
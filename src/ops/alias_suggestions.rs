@@ -11,6 +11,10 @@ pub struct AliasSuggestion {
     pub reason: String,
 }
 
+/// How many of the top suggestions also get a variant keeping the command's
+/// privilege wrapper.
+const WRAPPED_VARIANTS: usize = 3;
+
 #[derive(Debug)]
 pub struct AliasSuggester {
     existing_aliases: HashSet<String>,
@@ -97,6 +101,37 @@ impl AliasSuggester {
         suggestions
     }
 
+    /// Suggestions for `command`. When it is run under `wrapper` (e.g. `sudo`), the
+    /// top few are each followed by a variant that keeps the wrapper, named with its
+    /// initial: `ssr` for `sudo systemctl restart` next to `sr`.
+    pub fn suggest_aliases_wrapped(&self, command: &str, wrapper: Option<&str>) -> Vec<AliasSuggestion> {
+        let plain = self.suggest_aliases(command);
+        let Some(wrapper) = wrapper else {
+            return plain;
+        };
+        let program = wrapper.split_whitespace().next().unwrap_or(wrapper);
+        let initial = program.chars().next().unwrap_or('s');
+        let taken: HashSet<&str> = plain.iter().map(|s| s.alias.as_str()).collect();
+
+        let mut out = Vec::with_capacity(plain.len() + WRAPPED_VARIANTS);
+        for (i, suggestion) in plain.iter().enumerate() {
+            out.push(suggestion.clone());
+            if i >= WRAPPED_VARIANTS {
+                continue;
+            }
+            let alias = format!("{initial}{}", suggestion.alias);
+            if taken.contains(alias.as_str()) || self.has_conflicts(&alias) {
+                continue;
+            }
+            out.push(AliasSuggestion {
+                alias,
+                command: format!("{wrapper} {command}"),
+                reason: format!("{} (keeps {program})", suggestion.reason),
+            });
+        }
+        out
+    }
+
     fn generate_semantic_aliases(&self, command: &str) -> Vec<AliasSuggestion> {
         let mut suggestions = Vec::new();
         let parts: Vec<&str> = command.split_whitespace().collect();
@@ -1,4 +1,4 @@
-use crate::registry::{self, DefinitionKind};
+use crate::registry::{self, DefinitionKind, RawBodies};
 use crate::template::{CommandTemplate, TemplatePart};
 use rusqlite::Connection;
 
@@ -14,15 +14,21 @@ pub enum ApplyOutcome {
 /// Insert OR IGNORE into `dismissed`, then delete from `command_stats`.
 fn dismiss_command(conn: &Connection, cmd: &str) -> Result<(), AlmanError> {
     let tx = conn.unchecked_transaction()?;
-    tx.execute(
+    dismiss_in(&tx, cmd)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// The body of [`dismiss_command`], for callers already inside a transaction.
+fn dismiss_in(conn: &Connection, cmd: &str) -> Result<(), AlmanError> {
+    conn.execute(
         "INSERT OR IGNORE INTO dismissed (command_text) VALUES (?1)",
         rusqlite::params![cmd],
     )?;
-    tx.execute(
+    conn.execute(
         "DELETE FROM command_stats WHERE command_text = ?1",
         rusqlite::params![cmd],
     )?;
-    tx.commit()?;
     Ok(())
 }
 
@@ -35,11 +41,36 @@ fn undismiss_command(conn: &Connection, cmd: &str) -> Result<(), AlmanError> {
     Ok(())
 }
 
+/// The literal tokens of `template` joined by spaces; what gets dismissed from
+/// `command_stats` when the template is saved.
+fn literal_skeleton(template: &CommandTemplate) -> String {
+    template
+        .parts
+        .iter()
+        .filter_map(|p| {
+            if let TemplatePart::Literal(s) = p {
+                Some(s.as_str())
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Refuse to turn the variable `name` into a command, as `apply_env_set`
+/// refuses the other way round.
+fn ensure_not_env(conn: &Connection, name: &str) -> Result<(), AlmanError> {
+    if registry::find_definition(conn, name)?.is_some_and(|d| d.kind.is_env()) {
+        return Err(format!("`{name}` is a variable; remove it with `alman env unset` first").into());
+    }
+    Ok(())
+}
+
 /// Add a plain alias: template = [Literal(command)].
 pub fn apply_add(conn: &Connection, name: &str, command: &str) -> Result<ApplyOutcome, AlmanError> {
-    let template = CommandTemplate {
-        parts: vec![TemplatePart::Literal(command.to_string())],
-    };
+    ensure_not_env(conn, name)?;
+    let template = CommandTemplate::new(vec![TemplatePart::Literal(command.to_string())]);
     registry::upsert_definition(conn, name, DefinitionKind::Alias, &template)?;
     dismiss_command(conn, command)?;
     Ok(ApplyOutcome::Added {
@@ -47,31 +78,27 @@ pub fn apply_add(conn: &Connection, name: &str, command: &str) -> Result<ApplyOu
     })
 }
 
+/// Add from the template mini-language (see [`CommandTemplate::parse`]): a
+/// plain alias when `text` has no placeholders, otherwise a definition shaped
+/// by its slots.
+pub fn apply_add_template(conn: &Connection, name: &str, text: &str) -> Result<ApplyOutcome, AlmanError> {
+    let template = CommandTemplate::parse(text)?;
+    match template.parts.as_slice() {
+        [TemplatePart::Literal(command)] if template.slots.is_empty() => apply_add(conn, name, command),
+        _ => apply_add_function(conn, name, &template),
+    }
+}
+
 /// Add a parameterized function (or alias if the shape allows it).
 pub fn apply_add_function(
     conn: &Connection,
     name: &str,
     template: &CommandTemplate,
 ) -> Result<ApplyOutcome, AlmanError> {
-    let kind = if template.is_zero_slot() || template.only_trailing_single_slot() {
-        DefinitionKind::Alias
-    } else {
-        DefinitionKind::Function
-    };
+    ensure_not_env(conn, name)?;
+    let kind = DefinitionKind::for_template(template);
     registry::upsert_definition(conn, name, kind, template)?;
-    // Dismiss the literal skeleton (literal tokens only, for command_stats).
-    let skeleton: String = template
-        .parts
-        .iter()
-        .filter_map(|p| {
-            if let TemplatePart::Literal(s) = p {
-                Some(s.as_str())
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>()
-        .join(" ");
+    let skeleton = literal_skeleton(template);
     if !skeleton.is_empty() {
         let _ = dismiss_command(conn, &skeleton);
     }
@@ -80,6 +107,57 @@ pub fn apply_add_function(
     })
 }
 
+/// Add or update a raw function. Bodies given here replace those of the same
+/// dialect; an empty body drops its dialect, and the rest are kept.
+pub fn apply_add_raw(conn: &Connection, name: &str, bodies: RawBodies) -> Result<ApplyOutcome, AlmanError> {
+    ensure_not_env(conn, name)?;
+    let mut merged = match registry::find_definition(conn, name)? {
+        Some(registry::Definition { kind: DefinitionKind::Raw(existing), .. }) => existing,
+        _ => RawBodies::default(),
+    };
+    for (dialect, body) in bodies.0 {
+        if body.trim().is_empty() {
+            merged.0.remove(&dialect);
+        } else {
+            merged.0.insert(dialect, body);
+        }
+    }
+    if merged.0.is_empty() {
+        return Err(format!("`{name}` would have no bodies left; use `alman remove {name}` instead").into());
+    }
+    registry::upsert_definition(conn, name, DefinitionKind::Raw(merged), &CommandTemplate::default())?;
+    Ok(ApplyOutcome::Added {
+        name: name.to_string(),
+    })
+}
+
+/// Export `name` with `value`, replacing its previous value.
+pub fn apply_env_set(conn: &Connection, name: &str, value: &str) -> Result<ApplyOutcome, AlmanError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("`{name}` is not a valid variable name").into());
+    }
+    if let Some(def) = registry::find_definition(conn, name)?.filter(|d| !d.kind.is_env()) {
+        return Err(format!("`{name}` is already an {}", def.kind.as_str()).into());
+    }
+    registry::upsert_definition(conn, name, DefinitionKind::Env(value.to_string()), &CommandTemplate::default())?;
+    Ok(ApplyOutcome::Added {
+        name: name.to_string(),
+    })
+}
+
+/// Stop exporting `name`; aliases and functions of that name are left alone.
+pub fn apply_env_unset(conn: &Connection, name: &str) -> Result<ApplyOutcome, AlmanError> {
+    match registry::find_definition(conn, name)? {
+        Some(def) if def.kind.is_env() => apply_remove(conn, name),
+        _ => Ok(ApplyOutcome::NotFound {
+            name: name.to_string(),
+        }),
+    }
+}
+
 /// Remove by name; un-dismiss its literal command if it was a zero-slot alias.
 pub fn apply_remove(conn: &Connection, name: &str) -> Result<ApplyOutcome, AlmanError> {
     let defs = registry::list_definitions(conn)?;
@@ -117,6 +195,72 @@ pub fn apply_change(
     Ok(ApplyOutcome::Changed)
 }
 
+/// Refuse edits `def` cannot take: variables are changed with `alman env set`,
+/// and the bodies of raw functions with `alman add-raw`. `new_body` is whether
+/// the edit replaces the body rather than only renaming.
+pub fn ensure_editable(def: &registry::Definition, new_body: bool) -> Result<(), AlmanError> {
+    match def.kind {
+        DefinitionKind::Env(_) => {
+            Err(format!("`{}` is a variable; change it with `alman env set`", def.name).into())
+        }
+        DefinitionKind::Raw(_) if new_body => {
+            Err(format!("`{}` is a raw function; change its bodies with `alman add-raw`", def.name).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Give `name` a new body and/or a new name, all or nothing. The creation time
+/// is kept; the old command can be suggested again and the new one is
+/// dismissed. Recorded slot values are dropped when the slots changed, since
+/// they no longer line up.
+pub fn apply_edit(
+    conn: &Connection,
+    name: &str,
+    template: Option<&CommandTemplate>,
+    rename: Option<&str>,
+) -> Result<ApplyOutcome, AlmanError> {
+    let Some(def) = registry::find_definition(conn, name)? else {
+        return Ok(ApplyOutcome::NotFound {
+            name: name.to_string(),
+        });
+    };
+    ensure_editable(&def, template.is_some())?;
+    let rename = rename.filter(|n| *n != name);
+    if let Some(new_name) = rename {
+        if registry::definition_exists(conn, new_name)? {
+            return Err(format!("`{new_name}` already exists").into());
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    if let Some(template) = template {
+        let kind = DefinitionKind::for_template(template);
+        registry::update_definition(&tx, name, kind, template)?;
+        let old = literal_skeleton(&def.template);
+        let new = literal_skeleton(template);
+        if old != new {
+            if !old.is_empty() {
+                undismiss_command(&tx, &old)?;
+            }
+            if !new.is_empty() {
+                dismiss_in(&tx, &new)?;
+            }
+        }
+        if def.template.max_slot() != template.max_slot() {
+            tx.execute(
+                "DELETE FROM slot_values WHERE definition = ?1",
+                rusqlite::params![name],
+            )?;
+        }
+    }
+    if let Some(new_name) = rename {
+        registry::rename_definition(&tx, name, new_name)?;
+    }
+    tx.commit()?;
+    Ok(ApplyOutcome::Changed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,17 +320,64 @@ mod tests {
         assert!(registry::definition_exists(&conn, "gaa").unwrap());
     }
 
+    fn is_dismissed(conn: &Connection, cmd: &str) -> bool {
+        conn.query_row(
+            "SELECT 1 FROM dismissed WHERE command_text = ?1",
+            [cmd],
+            |_| Ok(true),
+        )
+        .unwrap_or(false)
+    }
+
+    #[test]
+    fn edit_replaces_command_and_keeps_created_at() {
+        let conn = open_mem();
+        apply_add(&conn, "ga", "git add -A").unwrap();
+        conn.execute("UPDATE definitions SET created_at = 42 WHERE name = 'ga'", []).unwrap();
+
+        let template = CommandTemplate::new(vec![
+            TemplatePart::Literal("git".into()),
+            TemplatePart::Literal("add".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("-v".into()),
+        ]);
+        apply_edit(&conn, "ga", Some(&template), Some("gav")).unwrap();
+
+        let def = registry::find_definition(&conn, "gav").unwrap().unwrap();
+        assert_eq!(def.template, template);
+        assert_eq!(def.kind, DefinitionKind::Function);
+        let created: i64 = conn
+            .query_row("SELECT created_at FROM definitions WHERE name = 'gav'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(created, 42);
+        assert!(!is_dismissed(&conn, "git add -A"));
+        assert!(is_dismissed(&conn, "git add -v"));
+        assert!(matches!(apply_edit(&conn, "ga", None, None).unwrap(), ApplyOutcome::NotFound { .. }));
+    }
+
+    #[test]
+    fn edit_onto_a_taken_name_changes_nothing() {
+        let conn = open_mem();
+        apply_add(&conn, "gs", "git status").unwrap();
+        apply_add(&conn, "gl", "git log").unwrap();
+
+        let template = CommandTemplate::new(vec![TemplatePart::Literal("git status -sb".into())]);
+        assert!(apply_edit(&conn, "gs", Some(&template), Some("gl")).is_err());
+
+        let gs = registry::find_definition(&conn, "gs").unwrap().unwrap();
+        assert_eq!(gs.template.parts, vec![TemplatePart::Literal("git status".into())]);
+        assert!(!is_dismissed(&conn, "git status -sb"));
+    }
+
     #[test]
     fn add_function_with_interior_slot_is_function() {
         let conn = open_mem();
-        let template = CommandTemplate {
-            parts: vec![
-                TemplatePart::Literal("docker".into()),
-                TemplatePart::Literal("exec".into()),
-                TemplatePart::Slot(1),
-                TemplatePart::Literal("bash".into()),
-            ],
-        };
+        let template = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("bash".into()),
+        ]);
         apply_add_function(&conn, "dex", &template).unwrap();
         let defs = registry::list_definitions(&conn).unwrap();
         let d = defs.iter().find(|d| d.name == "dex").unwrap();
@@ -194,11 +385,91 @@ mod tests {
     }
 
     #[test]
-    fn add_function_with_trailing_slot_is_alias() {
+    fn add_template_picks_kind_by_shape() {
+        let conn = open_mem();
+        apply_add_template(&conn, "gs", "git status").unwrap();
+        apply_add_template(&conn, "dex", "docker exec -it {container} bash").unwrap();
+        let gs = registry::find_definition(&conn, "gs").unwrap().unwrap();
+        assert_eq!(gs.template.parts, vec![TemplatePart::Literal("git status".into())]);
+        let dex = registry::find_definition(&conn, "dex").unwrap().unwrap();
+        assert_eq!(dex.kind, DefinitionKind::Function);
+        assert_eq!(dex.template.slot_name(1), "container");
+        assert!(apply_add_template(&conn, "bad", "cp {1} {3}").is_err());
+        assert!(!registry::definition_exists(&conn, "bad").unwrap());
+    }
+
+    #[test]
+    fn add_raw_merges_bodies_by_dialect() {
         let conn = open_mem();
-        let template = CommandTemplate {
-            parts: vec![TemplatePart::Literal("git".into()), TemplatePart::Slot(1)],
+        let bodies = |pairs: &[(&str, &str)]| {
+            RawBodies(pairs.iter().map(|(d, b)| (d.to_string(), b.to_string())).collect())
         };
+        apply_add_raw(&conn, "mkcd", bodies(&[("posix", "mkdir -p \"$1\" && cd \"$1\"")])).unwrap();
+        apply_add_raw(&conn, "mkcd", bodies(&[("fish", "mkdir -p $argv[1]; and cd $argv[1]")])).unwrap();
+
+        let def = registry::find_definition(&conn, "mkcd").unwrap().unwrap();
+        let DefinitionKind::Raw(stored) = &def.kind else { panic!("expected a raw definition") };
+        assert_eq!(stored.dialects(), vec!["fish", "posix"]);
+        assert_eq!(stored.body_for("zsh"), Some("mkdir -p \"$1\" && cd \"$1\""));
+
+        apply_add_raw(&conn, "mkcd", bodies(&[("posix", "")])).unwrap();
+        let def = registry::find_definition(&conn, "mkcd").unwrap().unwrap();
+        let DefinitionKind::Raw(stored) = &def.kind else { panic!("expected a raw definition") };
+        assert_eq!(stored.body_for("bash"), None);
+        assert!(apply_add_raw(&conn, "mkcd", bodies(&[("fish", " ")])).is_err());
+    }
+
+    #[test]
+    fn env_set_and_unset_only_touch_variables() {
+        let conn = open_mem();
+        apply_env_set(&conn, "EDITOR", "vim").unwrap();
+        apply_env_set(&conn, "EDITOR", "nvim").unwrap();
+        let def = registry::find_definition(&conn, "EDITOR").unwrap().unwrap();
+        assert_eq!(def.kind, DefinitionKind::Env("nvim".into()));
+
+        apply_add(&conn, "gs", "git status").unwrap();
+        assert!(apply_env_set(&conn, "gs", "x").is_err());
+        assert!(apply_env_set(&conn, "NOT-VALID", "x").is_err());
+        assert!(matches!(apply_env_unset(&conn, "gs").unwrap(), ApplyOutcome::NotFound { .. }));
+        assert!(registry::definition_exists(&conn, "gs").unwrap());
+
+        // Nor does adding a command replace a variable.
+        assert!(apply_add(&conn, "EDITOR", "vim").is_err());
+        let template = CommandTemplate::parse("vim {1}").unwrap();
+        assert!(apply_add_function(&conn, "EDITOR", &template).is_err());
+        let def = registry::find_definition(&conn, "EDITOR").unwrap().unwrap();
+        assert_eq!(def.kind, DefinitionKind::Env("nvim".into()));
+
+        apply_env_unset(&conn, "EDITOR").unwrap();
+        assert!(!registry::definition_exists(&conn, "EDITOR").unwrap());
+    }
+
+    #[test]
+    fn edit_keeps_raw_bodies() {
+        let conn = open_mem();
+        let bodies = RawBodies([("posix".to_string(), "mkdir -p \"$1\" && cd \"$1\"".to_string())].into());
+        apply_add_raw(&conn, "mkcd", bodies.clone()).unwrap();
+        let template = CommandTemplate::new(vec![TemplatePart::Literal("mkdir -p".into())]);
+        assert!(apply_edit(&conn, "mkcd", Some(&template), None).is_err());
+        apply_edit(&conn, "mkcd", None, Some("mcd")).unwrap();
+        let def = registry::find_definition(&conn, "mcd").unwrap().unwrap();
+        assert_eq!(def.kind, DefinitionKind::Raw(bodies));
+    }
+
+    #[test]
+    fn edit_leaves_variables_alone() {
+        let conn = open_mem();
+        apply_env_set(&conn, "KUBECONFIG", "~/.kube/dev").unwrap();
+        let template = CommandTemplate::new(vec![TemplatePart::Literal("echo hi".into())]);
+        assert!(apply_edit(&conn, "KUBECONFIG", Some(&template), None).is_err());
+        let def = registry::find_definition(&conn, "KUBECONFIG").unwrap().unwrap();
+        assert_eq!(def.kind, DefinitionKind::Env("~/.kube/dev".into()));
+    }
+
+    #[test]
+    fn add_function_with_trailing_slot_is_alias() {
+        let conn = open_mem();
+        let template = CommandTemplate::new(vec![TemplatePart::Literal("git".into()), TemplatePart::Slot(1)]);
         apply_add_function(&conn, "g", &template).unwrap();
         let defs = registry::list_definitions(&conn).unwrap();
         let d = defs.iter().find(|d| d.name == "g").unwrap();
@@ -0,0 +1,105 @@
+use crate::ops::apply::AlmanError;
+use crate::registry::Definition;
+use crate::template::CommandTemplate;
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+
+/// The body of `def` as editable text, in the template mini-language.
+pub fn definition_text(def: &Definition) -> String {
+    def.template.to_text()
+}
+
+/// Parse text produced by [`definition_text`] after editing. `#` lines are
+/// comments; `None` when nothing is left.
+pub fn parse_definition_text(text: &str) -> Result<Option<CommandTemplate>, AlmanError> {
+    let body = text
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(CommandTemplate::parse(&body)?))
+}
+
+/// The new template from `alman edit`'s `--command` (template mini-language)
+/// or `--template` (JSON) option.
+pub fn template_from_args(
+    command: Option<&str>,
+    template: Option<&str>,
+) -> Result<Option<CommandTemplate>, AlmanError> {
+    match (command, template) {
+        (Some(command), _) => Ok(Some(CommandTemplate::parse(command)?)),
+        (None, Some(json)) => CommandTemplate::from_json(json)
+            .map(Some)
+            .ok_or_else(|| "the template is not valid JSON".into()),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Open `def` in `$VISUAL` / `$EDITOR` (falling back to `vi`) and return the
+/// edited template; `None` when the file was emptied or left unchanged.
+pub fn edit_in_editor(def: &Definition) -> Result<Option<CommandTemplate>, AlmanError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Created fresh and private to the user, so nothing planted in the temp
+    // directory can be followed or read; removed when dropped.
+    let mut buffer = tempfile::Builder::new().prefix("alman-edit-").suffix(".txt").tempfile()?;
+    let original = definition_text(def);
+    write!(
+        buffer,
+        "# Editing {} `{}`. Save and quit to apply; an empty file cancels.\n\
+         # Write {{1}} or {{name}} where an argument goes, {{name=default}} for a default, {{...}} for the rest.\n{}\n",
+        def.kind.as_str(),
+        def.name,
+        original
+    )?;
+
+    // Through the shell so editors configured with arguments (`code --wait`) work.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(buffer.path())
+        .status();
+    let edited = fs::read_to_string(buffer.path());
+    if !status?.success() {
+        return Err(format!("{editor} exited with an error").into());
+    }
+
+    let template = parse_definition_text(&edited?)?;
+    Ok(template.filter(|t| *t != def.template))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::DefinitionKind;
+    use crate::template::TemplatePart;
+
+    #[test]
+    fn definition_text_round_trips() {
+        let plain = Definition {
+            name: "gs".into(),
+            kind: DefinitionKind::Alias,
+            template: CommandTemplate::new(vec![TemplatePart::Literal("git status -sb".into())]),
+        };
+        assert_eq!(definition_text(&plain), "git status -sb");
+        let edited = parse_definition_text("# comment\ngit status\n").unwrap().unwrap();
+        assert_eq!(edited.parts, vec![TemplatePart::Literal("git status".into())]);
+
+        let dex = Definition {
+            name: "dex".into(),
+            kind: DefinitionKind::Function,
+            template: CommandTemplate::new(vec![TemplatePart::Literal("docker".into()), TemplatePart::Slot(1)]),
+        };
+        let text = definition_text(&dex);
+        assert_eq!(parse_definition_text(&text).unwrap(), Some(dex.template));
+        assert!(parse_definition_text("# only a comment\n\n").unwrap().is_none());
+        assert_eq!(text, "docker {1}");
+        assert!(parse_definition_text("docker {2}").is_err());
+    }
+}
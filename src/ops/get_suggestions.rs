@@ -1,7 +1,13 @@
 use crate::database::database_structs::Command;
 use crate::database::db::now_secs;
+use crate::database::history_loader::command_prefixes;
 use crate::ops::alias_suggestions::{AliasSuggester, AliasSuggestion};
 use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Most distinct commands read from the events under a scope, most used first.
+const SCOPED_COMMANDS: i64 = 500;
 
 #[derive(Debug, Clone)]
 pub struct CommandWithAlias {
@@ -13,41 +19,92 @@ pub fn get_suggestions_with_aliases(
     num: Option<usize>,
     conn: &Connection,
     alias_file_path: &str,
+    scope: Option<&Path>,
 ) -> Vec<CommandWithAlias> {
     let limit = num.unwrap_or(5) as i64;
     let now = now_secs();
 
-    let commands = query_top_commands(conn, now, limit);
+    let commands = query_top_commands(conn, now, limit, scope);
     let paths = vec![alias_file_path.to_string()];
     let suggester = AliasSuggester::new(&paths);
 
     commands
         .into_iter()
         .map(|cmd| {
-            let alias_suggestions = suggester.suggest_aliases(&cmd.command_text);
+            let alias_suggestions = suggester.suggest_aliases_wrapped(&cmd.command_text, cmd.wrapper.as_deref());
             CommandWithAlias { command: cmd, alias_suggestions }
         })
         .collect()
 }
 
+/// The directory suggestions are scoped to: the git repository root containing
+/// `dir`, or `dir` itself when it is not inside a repository.
+pub fn resolve_scope(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// Scope for the current working directory. Prefers the shell's logical `$PWD`,
+/// which is what the hooks record, over the symlink-resolved process cwd.
+pub fn current_scope() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    let logical = std::env::var_os("PWD")
+        .map(PathBuf::from)
+        .filter(|p| p.canonicalize().ok() == cwd.canonicalize().ok())
+        .unwrap_or(cwd);
+    Some(resolve_scope(&logical))
+}
+
 /// Fetch the top `limit` commands by score, excluding dismissed entries.
-pub fn query_top_commands(conn: &Connection, now: i64, limit: i64) -> Vec<Command> {
-    query_filtered_commands(conn, now, "", limit)
+pub fn query_top_commands(conn: &Connection, now: i64, limit: i64, scope: Option<&Path>) -> Vec<Command> {
+    query_filtered_commands(conn, now, "", limit, scope)
 }
 
 /// Fetch up to `limit` commands matching `filter` (case-insensitive substring),
 /// ordered by score, excluding dismissed entries.
-pub fn query_filtered_commands(conn: &Connection, now: i64, filter: &str, limit: i64) -> Vec<Command> {
+///
+/// With a `scope`, commands run inside that directory tree come first, ranked by
+/// their local frequency and recency; the global ranking fills the remainder.
+pub fn query_filtered_commands(
+    conn: &Connection,
+    now: i64,
+    filter: &str,
+    limit: i64,
+    scope: Option<&Path>,
+) -> Vec<Command> {
+    let global = query_global_commands(conn, now, filter, limit);
+    let Some(scope) = scope else {
+        return global;
+    };
+
+    let mut local = query_scoped_commands(conn, now, filter, scope);
+    local.truncate(limit.max(0) as usize);
+    let seen: HashSet<String> = local.iter().map(|c| c.command_text.clone()).collect();
+    let remaining = (limit.max(0) as usize).saturating_sub(local.len());
+    local.extend(
+        global
+            .into_iter()
+            .filter(|c| !seen.contains(&c.command_text))
+            .take(remaining),
+    );
+    local
+}
+
+fn query_global_commands(conn: &Connection, now: i64, filter: &str, limit: i64) -> Vec<Command> {
     let sql = if filter.is_empty() {
         "SELECT command_text, frequency, last_access_time,
-                alman_score(frequency, last_access_time, length, ?1) AS score
+                alman_score(frequency, last_access_time, length, ?1, failures) AS score,
+                failures, wrapper
          FROM command_stats
          WHERE command_text NOT IN (SELECT command_text FROM dismissed)
          ORDER BY score DESC
          LIMIT ?2"
     } else {
         "SELECT command_text, frequency, last_access_time,
-                alman_score(frequency, last_access_time, length, ?1) AS score
+                alman_score(frequency, last_access_time, length, ?1, failures) AS score,
+                failures, wrapper
          FROM command_stats
          WHERE command_text NOT IN (SELECT command_text FROM dismissed)
            AND LOWER(command_text) LIKE '%' || LOWER(?3) || '%'
@@ -66,6 +123,9 @@ pub fn query_filtered_commands(conn: &Connection, now: i64, filter: &str, limit:
             frequency:        row.get(1)?,
             last_access_time: row.get(2)?,
             score:            row.get(3)?,
+            failures:         row.get(4)?,
+            wrapper:          row.get(5)?,
+            local_frequency:  0,
         })
     };
 
@@ -80,3 +140,133 @@ pub fn query_filtered_commands(conn: &Connection, now: i64, filter: &str, limit:
         Err(e) => { eprintln!("alman: query error: {e}"); vec![] }
     }
 }
+
+/// Commands with at least one event recorded under `scope`, scored on those events alone.
+/// Only prefixes that still have a (non-dismissed) `command_stats` row are returned.
+fn query_scoped_commands(conn: &Connection, now: i64, filter: &str, scope: &Path) -> Vec<Command> {
+    let scope = scope.to_string_lossy();
+    let scope = scope.trim_end_matches('/');
+    // `cwd >= 'dir/' AND cwd < 'dir0'` matches every subdirectory and can use idx_events_cwd.
+    let lower = format!("{scope}/");
+    let upper = format!("{scope}0");
+
+    // Per distinct command; failures as `is_failure` counts them (130 and 148 are Ctrl-C and Ctrl-Z).
+    let commands: Vec<(String, i64, i64, i64)> = {
+        let mut stmt = match conn.prepare(
+            "SELECT command, COUNT(*) AS uses, MAX(ts),
+                    SUM(exit_code IS NOT NULL AND exit_code NOT IN (0, 130, 148))
+             FROM events
+             WHERE cwd = ?1 OR (cwd >= ?2 AND cwd < ?3)
+             GROUP BY command
+             ORDER BY uses DESC, MAX(ts) DESC
+             LIMIT ?4",
+        ) {
+            Ok(s) => s,
+            Err(e) => { eprintln!("alman: prepare error: {e}"); return vec![]; }
+        };
+        let rows = stmt.query_map(rusqlite::params![scope, lower, upper, SCOPED_COMMANDS], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+        });
+        match rows {
+            Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
+            Err(e) => { eprintln!("alman: query error: {e}"); return vec![]; }
+        }
+    };
+
+    // prefix -> (local frequency, last local use, local failures)
+    let mut counts: HashMap<String, (i64, i64, i64)> = HashMap::new();
+    for (command, uses, last, failures) in &commands {
+        for prefix in command_prefixes(command) {
+            let entry = counts.entry(prefix.text).or_insert((0, 0, 0));
+            entry.0 += uses;
+            entry.1 = entry.1.max(*last);
+            entry.2 += failures;
+        }
+    }
+
+    let filter = filter.to_lowercase();
+    let scorer = crate::defaults::default_relevance_scorer();
+    let mut stmt = match conn.prepare(
+        "SELECT frequency, last_access_time, length, failures, wrapper FROM command_stats
+         WHERE command_text = ?1
+           AND command_text NOT IN (SELECT command_text FROM dismissed)",
+    ) {
+        Ok(s) => s,
+        Err(e) => { eprintln!("alman: prepare error: {e}"); return vec![]; }
+    };
+
+    let mut out: Vec<Command> = counts
+        .into_iter()
+        .filter(|(text, _)| filter.is_empty() || text.to_lowercase().contains(&filter))
+        .filter_map(|(text, (local_freq, local_last, local_failures))| {
+            let (frequency, last_access_time, length, failures, wrapper): (i64, i64, i64, i64, Option<String>) = stmt
+                .query_row(rusqlite::params![text], |r| {
+                    Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+                })
+                .ok()?;
+            let score = scorer.score(
+                local_freq as f64,
+                local_last,
+                length as f64,
+                now,
+                local_failures as f64,
+            );
+            Some(Command {
+                command_text: text,
+                frequency,
+                last_access_time,
+                score,
+                failures,
+                wrapper,
+                local_frequency: local_freq,
+            })
+        })
+        .collect();
+
+    out.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.command_text.cmp(&b.command_text))
+    });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::insert_command::insert_command;
+
+    #[test]
+    fn scoped_query_ranks_local_commands_first() {
+        let conn = crate::database::db::open(":memory:").unwrap();
+        for _ in 0..5 {
+            insert_command("git status".into(), &conn, None, Some("/home/u/other"), Some(0), None);
+        }
+        insert_command("make deploy-staging".into(), &conn, None, Some("/home/u/proj/sub"), Some(0), None);
+        insert_command("make deploy-staging".into(), &conn, None, Some("/home/u/proj"), Some(2), None);
+        insert_command("make lint".into(), &conn, None, Some("/home/u/project-two"), Some(0), None);
+
+        let now = now_secs();
+        let global = query_top_commands(&conn, now, 10, None);
+        assert_eq!(global[0].command_text, "git status");
+
+        let scoped = query_top_commands(&conn, now, 10, Some(Path::new("/home/u/proj")));
+        assert_eq!(scoped[0].command_text, "make deploy-staging");
+        assert_eq!(scoped[0].local_frequency, 2);
+        // Sibling directory sharing the name prefix is not in scope.
+        let lint = scoped.iter().find(|c| c.command_text == "make lint").unwrap();
+        assert_eq!(lint.local_frequency, 0);
+        // Global results still fill the remainder.
+        assert!(scoped.iter().any(|c| c.command_text == "git status"));
+    }
+
+    #[test]
+    fn resolve_scope_finds_git_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        let nested = dir.path().join("src/bin");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(resolve_scope(&nested), dir.path());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::ast::Token;
+use crate::mining::miner::{MinedTemplate, TokenizedEvent};
+use rusqlite::Connection;
+
+const RECENT_EVENTS: i64 = 2000;
+
+/// Mine templates from the most recent events with the default miner.
+pub fn mine_recent_templates(conn: &Connection) -> rusqlite::Result<Vec<MinedTemplate>> {
+    let tokenizer = crate::defaults::default_tokenizer();
+    let normalizer = crate::defaults::default_normalizer();
+    let mut stmt = conn.prepare(
+        "SELECT command, session_id, ts FROM events ORDER BY ts DESC, id DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map([RECENT_EVENTS], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?, r.get::<_, i64>(2)?))
+    })?;
+    let mut events: Vec<TokenizedEvent> = Vec::new();
+    // Lines whose operators don't parse (`| grep`, unbalanced parens) are skipped.
+    for (command, session_id, ts) in rows.filter_map(|r| r.ok()) {
+        let Some(mut list) = tokenizer.parse(&command) else { continue };
+        normalizer.normalize(&mut list);
+        events.push(TokenizedEvent { tokens: list.to_tokens(), session_id, ts });
+        // Segments and multi-command pipeline tails are mined on their own too. They
+        // carry no session so the sequence miner doesn't read them as separate steps.
+        let commands = list.simple_commands();
+        let mut parts: Vec<Vec<Token>> = Vec::new();
+        if commands.len() > 1 {
+            parts.extend(commands.iter().map(|c| c.to_tokens()));
+        }
+        parts.extend(
+            list.pipeline_tails()
+                .iter()
+                .filter(|tail| tail.commands.len() > 1)
+                .map(|tail| tail.to_tokens()),
+        );
+        events.extend(parts.into_iter().map(|tokens| TokenizedEvent { tokens, session_id: None, ts }));
+    }
+    events.retain(|e| !e.tokens.is_empty());
+    // Chronological order, so same-second events keep the order they were recorded in.
+    events.reverse();
+    Ok(crate::defaults::default_miner().mine(&events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::insert_command::insert_command;
+    use crate::template::{CommandTemplate, TemplatePart};
+
+    #[test]
+    fn mines_chained_commands_on_their_own() {
+        let conn = crate::database::db::open(":memory:").unwrap();
+        for (build, pod) in [("api", "web"), ("web", "db"), ("cli", "cache")] {
+            let line = format!("make {build} && kubectl logs {pod} | grep -v health");
+            insert_command(line, &conn, None, None, Some(0), None);
+        }
+        let templates: Vec<CommandTemplate> =
+            mine_recent_templates(&conn).unwrap().into_iter().map(|m| m.template).collect();
+        let logs = CommandTemplate::new(vec![
+            TemplatePart::Literal("kubectl".into()),
+            TemplatePart::Literal("logs".into()),
+            TemplatePart::Slot(1),
+        ]);
+        assert!(templates.contains(&logs), "got {templates:?}");
+    }
+}
@@ -1,21 +1,52 @@
 use crate::database::db::now_secs;
 use crate::database::history_loader::upsert_prefixes;
+use crate::registry;
 use rusqlite::Connection;
 
+/// Whether an exit status means the command failed.
+/// 130 (Ctrl-C) and 148 (Ctrl-Z) are user interruptions, not failures.
+pub fn is_failure(exit_code: i32) -> bool {
+    !matches!(exit_code, 0 | 130 | 148)
+}
+
 pub fn insert_command(
     command_str: String,
     conn: &Connection,
     session_id: Option<&str>,
     cwd: Option<&str>,
+    exit_code: Option<i32>,
+    duration_ms: Option<i64>,
 ) {
-    let command_str = command_str.trim().to_string();
-    if command_str.is_empty() {
-        return;
+    let tx = match conn.unchecked_transaction() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("alman: DB error starting transaction: {e}");
+            return;
+        }
+    };
+    if record_command(&tx, &command_str, None, session_id, cwd, exit_code, duration_ms).is_some() {
+        if let Err(e) = tx.commit() {
+            eprintln!("alman: DB error committing: {e}");
+        }
     }
+}
 
+/// The body of [`insert_command`], for callers already inside a transaction.
+/// `ts` is when the command ran, if not just now. `None` when the event could
+/// not be written.
+pub fn record_command(
+    conn: &Connection,
+    command_str: &str,
+    ts: Option<i64>,
+    session_id: Option<&str>,
+    cwd: Option<&str>,
+    exit_code: Option<i32>,
+    duration_ms: Option<i64>,
+) -> Option<()> {
+    let command_str = command_str.trim();
     let parts: Vec<&str> = command_str.split_whitespace().collect();
     if parts.is_empty() {
-        return;
+        return Some(());
     }
 
     // Skip commands that start with the alman binary itself
@@ -25,31 +56,91 @@ pub fn insert_command(
         .and_then(|s| s.into_string().ok());
     if let Some(ref name) = binary_name {
         if parts[0] == name.as_str() {
-            return;
+            return Some(());
         }
     }
 
-    let now = now_secs();
-
-    let tx = match conn.unchecked_transaction() {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("alman: DB error starting transaction: {e}");
-            return;
-        }
-    };
+    let now = ts.unwrap_or_else(now_secs);
 
-    if let Err(e) = tx.execute(
-        "INSERT INTO events (command, ts, session_id, cwd, exit_code) VALUES (?1, ?2, ?3, ?4, NULL)",
-        rusqlite::params![command_str, now, session_id, cwd],
+    if let Err(e) = conn.execute(
+        "INSERT INTO events (command, ts, session_id, cwd, exit_code, duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![command_str, now, session_id, cwd, exit_code, duration_ms],
     ) {
         eprintln!("alman: DB error inserting event: {e}");
+        return None;
+    }
+
+    record_definition_uses(conn, command_str, now);
+    upsert_prefixes(conn, command_str, now, exit_code.is_some_and(is_failure));
+    Some(())
+}
+
+/// Count every command in `command_str` that runs a saved alias or function,
+/// with the arguments it was given.
+fn record_definition_uses(conn: &Connection, command_str: &str, ts: i64) {
+    let Some(list) = crate::defaults::default_tokenizer().parse(command_str) else {
         return;
+    };
+    for command in list.simple_commands() {
+        let Some((name, args)) = command.words.split_first() else {
+            continue;
+        };
+        match registry::find_definition(conn, name) {
+            Ok(Some(def)) if def.kind.is_env() => {}
+            Ok(Some(def)) => {
+                if let Err(e) = registry::record_definition_use(conn, &def, args, ts) {
+                    eprintln!("alman: DB error recording use of {name}: {e}");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("alman: DB error looking up {name}: {e}"),
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db;
+    use crate::ops::apply::{apply_add, apply_add_function};
+    use crate::template::{CommandTemplate, TemplatePart};
+
+    #[test]
+    fn definition_runs_count_as_uses_not_commands() {
+        let conn = db::open(":memory:").unwrap();
+        apply_add(&conn, "gs", "git status").unwrap();
+        let dex = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Slot(2),
+        ]);
+        apply_add_function(&conn, "dex", &dex).unwrap();
+
+        insert_command("gs".into(), &conn, None, None, Some(0), None);
+        insert_command("gs --short && dex web bash".into(), &conn, None, None, Some(0), None);
+
+        let usage = registry::definition_usage(&conn).unwrap();
+        assert_eq!(usage["gs"].0, 2);
+        assert_eq!(usage["dex"].0, 1);
+        let values = registry::list_slot_values(&conn, "dex").unwrap();
+        assert_eq!(values[&1], vec!["web"]);
+        assert_eq!(values[&2], vec!["bash"]);
 
-    upsert_prefixes(&tx, &command_str, now);
+        // Counts observed while mining outweigh a single later use.
+        registry::record_slot_values(&conn, "dex", 1, &[("api".into(), 3), ("db".into(), 1)]).unwrap();
+        insert_command("dex db sh".into(), &conn, None, None, Some(0), None);
+        let values = registry::list_slot_values(&conn, "dex").unwrap();
+        assert_eq!(values[&1], vec!["api", "db", "web"]);
 
-    if let Err(e) = tx.commit() {
-        eprintln!("alman: DB error committing: {e}");
+        let stats: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM command_stats WHERE command_text LIKE 'gs%' OR command_text LIKE 'dex%'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(stats, 0, "alias names must not become suggestions");
     }
 }
@@ -0,0 +1,121 @@
+use crate::ast::Token;
+use crate::registry::{Definition, DefinitionKind};
+use crate::template::{Binding, TemplatePart};
+use crate::tokenize::{quote_word, tokens_to_text};
+use serde_json::json;
+
+/// A saved definition that produces a typed command, and how.
+pub struct DefinitionMatch<'a> {
+    pub def: &'a Definition,
+    pub binding: Binding,
+}
+
+impl DefinitionMatch<'_> {
+    /// What to type instead: the name, the bound arguments, then whatever followed.
+    pub fn invocation(&self) -> String {
+        let mut words = vec![self.def.name.clone()];
+        words.extend(self.binding.args().iter().map(|a| quote_word(a)));
+        if !self.binding.trailing.is_empty() {
+            words.push(tokens_to_text(&self.binding.trailing));
+        }
+        words.join(" ")
+    }
+}
+
+/// Every definition that can produce `command`, most specific first: the one
+/// matching the most literal words, then the shortest name.
+///
+/// Literals must match and slots bind any one word. Words left after the
+/// template are allowed for an alias, which the shell appends them to, but not
+/// for a function, which would drop them; a trailing pipe or list is fine for both.
+pub fn matching_definitions<'a>(defs: &'a [Definition], command: &str) -> Vec<DefinitionMatch<'a>> {
+    let Some(list) = crate::defaults::default_tokenizer().parse(command) else {
+        return Vec::new();
+    };
+    let tokens = list.to_tokens();
+    let has_literal =
+        |def: &Definition| def.template.parts.iter().any(|p| matches!(p, TemplatePart::Literal(_)));
+    let mut out: Vec<DefinitionMatch> = defs
+        .iter()
+        .filter(|def| has_literal(def))
+        .filter_map(|def| Some(DefinitionMatch { def, binding: def.template.bind(&tokens)? }))
+        .filter(|m| {
+            m.def.kind == DefinitionKind::Alias
+                || !matches!(m.binding.trailing.first(), Some(Token::Word(_)))
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        b.binding
+            .literal_tokens
+            .cmp(&a.binding.literal_tokens)
+            .then(a.def.name.len().cmp(&b.def.name.len()))
+    });
+    out
+}
+
+/// The matches as JSON: name, kind, what to type, and each slot's binding.
+pub fn matches_to_json(matches: &[DefinitionMatch]) -> serde_json::Value {
+    let items: Vec<serde_json::Value> = matches
+        .iter()
+        .map(|m| {
+            let template = &m.def.template;
+            let bindings: Vec<serde_json::Value> = m
+                .binding
+                .slots
+                .iter()
+                .map(|(n, value)| json!({ "slot": n, "name": template.slot_name(*n), "value": value }))
+                .collect();
+            json!({
+                "name": m.def.name,
+                "kind": m.def.kind.as_str(),
+                "invocation": m.invocation(),
+                "bindings": bindings,
+                "rest": m.binding.rest,
+                "trailing": tokens_to_text(&m.binding.trailing),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db;
+    use crate::ops::apply::{apply_add, apply_add_function};
+    use crate::registry;
+    use crate::template::CommandTemplate;
+
+    #[test]
+    fn prefers_the_most_specific_definition() {
+        let conn = db::open(":memory:").unwrap();
+        apply_add(&conn, "g", "git").unwrap();
+        apply_add(&conn, "gco", "git checkout").unwrap();
+        let defs = registry::list_definitions(&conn).unwrap();
+
+        let found = matching_definitions(&defs, "git checkout -b main");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].invocation(), "gco -b main");
+        assert!(matching_definitions(&defs, "gitk").is_empty());
+    }
+
+    #[test]
+    fn functions_do_not_take_extra_words() {
+        let conn = db::open(":memory:").unwrap();
+        let dex = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("bash".into()),
+        ]);
+        apply_add_function(&conn, "dex", &dex).unwrap();
+        let defs = registry::list_definitions(&conn).unwrap();
+
+        assert!(matching_definitions(&defs, "docker exec web bash -l").is_empty());
+        let found = matching_definitions(&defs, "docker exec web bash | tee log");
+        let json = matches_to_json(&found);
+        assert_eq!(json[0]["invocation"], "dex web | tee log");
+        assert_eq!(json[0]["kind"], "function");
+        assert_eq!(json[0]["bindings"][0], json!({ "slot": 1, "name": "arg1", "value": "web" }));
+    }
+}
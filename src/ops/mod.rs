@@ -1,6 +1,12 @@
 pub mod apply;
 pub mod get_suggestions;
+pub mod get_templates;
 pub mod insert_command;
 pub mod delete_suggestion;
 pub mod alias_ops;
 pub mod alias_suggestions;
+pub mod edit;
+pub mod lookup;
+pub mod remind;
+pub mod render_aliases;
+pub mod sync_history;
@@ -0,0 +1,98 @@
+use crate::ops::apply::AlmanError;
+use crate::ops::lookup::matching_definitions;
+use crate::registry;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// The same definition is pointed out at most once per hour.
+const REMIND_INTERVAL_SECS: i64 = 3600;
+
+/// A hint that is due, from [`due_reminder`].
+pub struct Reminder {
+    /// The definition it points out.
+    pub definition: String,
+    pub hint: String,
+}
+
+/// A one-line hint when a saved definition would have been shorter to type than
+/// `command` and was not pointed out in the last `REMIND_INTERVAL_SECS`. Only
+/// reads, so the hooks can ask with a read-only connection and record the hint
+/// with [`mark_shown`] only when there is one.
+pub fn due_reminder(conn: &Connection, command: &str, now: i64) -> Result<Option<Reminder>, AlmanError> {
+    let defs = registry::list_definitions(conn)?;
+    let command = command.trim();
+    let Some((found, invocation)) = matching_definitions(&defs, command)
+        .into_iter()
+        .map(|c| {
+            let invocation = c.invocation();
+            (c, invocation)
+        })
+        .find(|(_, invocation)| invocation.len() < command.len())
+    else {
+        return Ok(None);
+    };
+
+    let name = &found.def.name;
+    let last_shown: Option<i64> = conn
+        .query_row(
+            "SELECT last_shown FROM reminders WHERE definition = ?1",
+            params![name],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if last_shown.is_some_and(|t| now - t < REMIND_INTERVAL_SECS) {
+        return Ok(None);
+    }
+
+    let kind = found.def.kind.as_str();
+    Ok(Some(Reminder {
+        definition: name.clone(),
+        hint: format!("alman: you have the {kind} `{name}` for this: {invocation}"),
+    }))
+}
+
+/// Remember that `definition` was pointed out at `now`.
+pub fn mark_shown(conn: &Connection, definition: &str, now: i64) -> Result<(), AlmanError> {
+    conn.execute(
+        "INSERT INTO reminders (definition, last_shown) VALUES (?1, ?2)
+         ON CONFLICT(definition) DO UPDATE SET last_shown = excluded.last_shown",
+        params![definition, now],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db;
+    use crate::ops::apply::apply_add_function;
+    use crate::template::{CommandTemplate, TemplatePart};
+
+    #[test]
+    fn reminds_once_per_interval() {
+        let conn = db::open(":memory:").unwrap();
+        let dex = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Literal("-it".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("bash".into()),
+        ]);
+        apply_add_function(&conn, "dex", &dex).unwrap();
+
+        let due = due_reminder(&conn, "docker exec -it web bash", 1_000).unwrap().unwrap();
+        assert_eq!(due.hint, "alman: you have the function `dex` for this: dex web");
+        // Asking again changes nothing until the hint is marked as shown.
+        assert!(due_reminder(&conn, "docker exec -it db bash", 1_060).unwrap().is_some());
+        mark_shown(&conn, &due.definition, 1_000).unwrap();
+        assert!(due_reminder(&conn, "docker exec -it db bash", 1_060).unwrap().is_none());
+        assert!(due_reminder(&conn, "docker exec -it db bash", 1_000 + REMIND_INTERVAL_SECS).unwrap().is_some());
+        assert!(due_reminder(&conn, "dex web", 9_999).unwrap().is_none());
+
+        // The throttle follows a rename and goes with the definition.
+        crate::registry::rename_definition(&conn, "dex", "dx").unwrap();
+        assert!(due_reminder(&conn, "docker exec -it db bash", 1_060).unwrap().is_none());
+        crate::registry::remove_definition(&conn, "dx").unwrap();
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM reminders", [], |r| r.get(0)).unwrap();
+        assert_eq!(rows, 0);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::cli::cli_data::InitShell;
+use crate::database::persistence::get_data_directory;
+use crate::ops::apply::AlmanError;
+use crate::registry::DefinitionKind;
+use crate::shell::{render_shell_init, ShellOpts};
+use rusqlite::Connection;
+use std::fs;
+use std::path::PathBuf;
+
+/// Every definition in `shell`'s syntax, then their completions. Variables
+/// come first so aliases and functions can use them; definitions the shell
+/// cannot run are left as a comment, with a warning on stderr.
+pub fn render_aliases(conn: &Connection, shell: &InitShell) -> String {
+    let mut defs = crate::registry::list_definitions(conn).unwrap_or_default();
+    let renderer = crate::defaults::renderer_for(shell, &defs);
+    defs.sort_by_key(|d| !d.kind.is_env());
+    let mut out = String::new();
+    for def in &defs {
+        match renderer.render_definition_in(def, shell.dialect()) {
+            Some(rendered) => {
+                out.push_str(&rendered);
+                out.push('\n');
+            }
+            None => {
+                let reason = match &def.kind {
+                    DefinitionKind::Raw(bodies) if bodies.body_for(shell.dialect()).is_none() => {
+                        format!("it has no {} body", shell.dialect())
+                    }
+                    _ => format!("{} cannot run it", shell.dialect()),
+                };
+                eprintln!("alman: skipping `{}`: {}", def.name, reason);
+                out.push_str(&format!("# alman: skipping `{}`: {}\n", def.name, reason));
+            }
+        }
+    }
+    if let Some(completer) = crate::defaults::completion_renderer_for(shell) {
+        for def in &defs {
+            let history = crate::registry::list_slot_values(conn, &def.name).unwrap_or_default();
+            let slots = crate::render::completion::slot_completions(def, &history);
+            if let Some(completion) = completer.render_completion(&def.name, &slots) {
+                out.push_str(&completion);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// The file `alman init nu` writes for config.nu to `source`.
+pub fn nu_init_path() -> Result<PathBuf, AlmanError> {
+    Ok(get_data_directory()?.join("init.nu"))
+}
+
+/// Write the nushell hooks and definitions to [`nu_init_path`]. Nushell cannot
+/// eval generated code, so instead of piping `render-aliases` into the shell
+/// at startup it sources this file.
+pub fn write_nu_init(conn: &Connection) -> Result<PathBuf, AlmanError> {
+    let path = nu_init_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let script = format!(
+        "{}\n\n# Definitions, rewritten by alman whenever they change.\n{}",
+        render_shell_init(InitShell::Nu, &ShellOpts::new()).trim_end(),
+        render_aliases(conn, &InitShell::Nu)
+    );
+    fs::write(&path, script)?;
+    Ok(path)
+}
+
+/// Rewrite the nushell file after definitions change, if `alman init nu` made one.
+pub fn refresh_nu_init(conn: &Connection) {
+    if nu_init_path().is_ok_and(|path| path.exists()) {
+        if let Err(e) = write_nu_init(conn) {
+            eprintln!("alman: could not update the nushell definitions: {e}");
+        }
+    }
+}
+
+/// Bring the nushell file up to date and tell the user how to load a change.
+pub fn print_source_message(conn: &Connection) {
+    refresh_nu_init(conn);
+    let shell_path = std::env::var("SHELL").unwrap_or_default();
+    let command = if std::env::var_os("NU_VERSION").is_some() || shell_path.ends_with("/nu") {
+        format!("source {}", nu_init_path().map(|p| p.display().to_string()).unwrap_or_default())
+    } else if shell_path.contains("pwsh") {
+        ". $PROFILE".to_string()
+    } else if shell_path.contains("tcsh") || shell_path.ends_with("/csh") {
+        "source ~/.tcshrc".to_string()
+    } else if shell_path.contains("zsh") {
+        "source ~/.zshrc".to_string()
+    } else if shell_path.contains("bash") {
+        "source ~/.bashrc".to_string()
+    } else if shell_path.contains("fish") {
+        "source ~/.config/fish/config.fish".to_string()
+    } else {
+        "source your shell's config file".to_string()
+    };
+    println!("\nTo use your new aliases immediately, run: \x1b[32m{}\x1b[0m", command);
+}
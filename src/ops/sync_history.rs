@@ -0,0 +1,163 @@
+use crate::database::history_format;
+use crate::database::history_loader::{get_history_file_path, parse_history_file};
+use crate::ops::apply::AlmanError;
+use crate::ops::insert_command::record_command;
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Identity of a file's contents; a new inode means the file was replaced.
+#[cfg(unix)]
+fn inode(meta: &fs::Metadata) -> i64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino() as i64
+}
+
+#[cfg(not(unix))]
+fn inode(_meta: &fs::Metadata) -> i64 {
+    0
+}
+
+/// Remember that `path` has been read up to `offset`.
+fn save_mark(conn: &Connection, path: &str, inode: i64, offset: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO history_files (path, inode, offset) VALUES (?1, ?2, ?3)
+         ON CONFLICT(path) DO UPDATE SET inode = excluded.inode, offset = excluded.offset",
+        rusqlite::params![path, inode, offset as i64],
+    )?;
+    Ok(())
+}
+
+/// How `path` is keyed in `history_files`, so `~/.zsh_history` reached through
+/// a relative path or a symlink is still the same file.
+fn mark_key(path: &Path) -> String {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().into_owned()
+}
+
+/// Mark everything now in `path` as read, after it was ingested some other way.
+pub fn mark_history_file(conn: &Connection, path: &Path) {
+    if let Ok(meta) = fs::metadata(path) {
+        let _ = save_mark(conn, &mark_key(path), inode(&meta), meta.len());
+    }
+}
+
+/// Ingest the commands appended to `path` since the last call and return how
+/// many there were.
+///
+/// A file seen for the first time is read whole, unless it is `bootstrap_file`,
+/// the one bootstrap already read (see [`get_history_file_path`]), which is
+/// only marked. One that was replaced or
+/// truncated is read again from the start, as `tail -F` would. Only finished
+/// entries are taken, so one still being written waits for the next call.
+/// The binary histories of ksh93 and mksh are refused.
+pub fn sync_history_file(conn: &Connection, path: &Path, bootstrap_file: Option<&Path>) -> Result<usize, AlmanError> {
+    let key = mark_key(path);
+    let mut file = File::open(path)?;
+    let meta = file.metadata()?;
+    let inode = inode(&meta);
+
+    let mut header = [0u8; 2];
+    let read = file.read(&mut header)?;
+    if let Some(shell) = history_format::binary_history_shell(&header[..read]) {
+        return Err(format!("{} is {shell}'s binary history, which alman cannot read", path.display()).into());
+    }
+
+    // Taking the write lock first keeps two prompts' syncs from reading the same lines.
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    let mark: Option<(i64, i64)> = tx
+        .query_row(
+            "SELECT inode, offset FROM history_files WHERE path = ?1",
+            [&key],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+    let offset = match mark {
+        None if bootstrap_file.is_some_and(|b| mark_key(b) == key) => {
+            save_mark(&tx, &key, inode, meta.len())?;
+            tx.commit()?;
+            return Ok(0);
+        }
+        None => 0,
+        Some((seen, offset)) if seen == inode && offset as u64 <= meta.len() => offset as u64,
+        Some(_) => 0,
+    };
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let parser = crate::defaults::history_parser_for(history_format::detect(path, &bytes));
+    let complete = parser.complete_len(&bytes);
+
+    // Oldest first, as they ran.
+    let mut commands = parse_history_file(&*parser, &bytes[..complete]);
+    commands.reverse();
+    for (command, ts) in &commands {
+        record_command(&tx, command, *ts, None, None, None, None)
+            .ok_or("could not record a command")?;
+    }
+    save_mark(&tx, &key, inode, offset + complete as u64)?;
+    tx.commit()?;
+    Ok(commands.len())
+}
+
+/// Sync the user's history file when `hooks` are off, so commands from shells
+/// without alman's integration still count.
+pub fn sync_unless_hooked(conn: &Connection, hooks: bool) {
+    if hooks {
+        return;
+    }
+    if let Ok(path) = get_history_file_path() {
+        let path = Path::new(&path);
+        if let Err(e) = sync_history_file(conn, path, Some(path)) {
+            eprintln!("alman: could not sync {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db;
+    use std::io::Write;
+
+    fn event_commands(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn.prepare("SELECT command FROM events ORDER BY id").unwrap();
+        stmt.query_map([], |r| r.get(0)).unwrap().filter_map(|r| r.ok()).collect()
+    }
+
+    #[test]
+    fn sync_ingests_only_complete_new_lines() {
+        let conn = db::open_for_write(":memory:").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".sh_history");
+        fs::write(&path, "git status\n").unwrap();
+
+        // Bootstrap never read this file, so what it already holds counts.
+        assert_eq!(sync_history_file(&conn, &path, None).unwrap(), 1);
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "cargo build --release\nmake test\ndocker comp").unwrap();
+        assert_eq!(sync_history_file(&conn, &path, None).unwrap(), 2);
+        assert_eq!(event_commands(&conn), ["git status", "cargo build --release", "make test"]);
+
+        writeln!(file, "ose up").unwrap();
+        assert_eq!(sync_history_file(&conn, &path, None).unwrap(), 1);
+        assert_eq!(sync_history_file(&conn, &path, None).unwrap(), 0);
+
+        // Truncated (or replaced): read again from the start.
+        fs::write(&path, "kubectl get pods\n").unwrap();
+        assert_eq!(sync_history_file(&conn, &path, None).unwrap(), 1);
+        assert_eq!(event_commands(&conn).last().unwrap(), "kubectl get pods");
+
+        fs::write(&path, [0x81, 0x01, b'l', b's', 0]).unwrap();
+        assert!(sync_history_file(&conn, &path, None).unwrap_err().to_string().contains("ksh93"));
+
+        // The file bootstrap seeded from is only marked the first time.
+        let seeded = dir.path().join(".bash_history");
+        fs::write(&seeded, "git status\n").unwrap();
+        assert_eq!(sync_history_file(&conn, &seeded, Some(&seeded)).unwrap(), 0);
+        fs::write(&seeded, "git status\nmake test\n").unwrap();
+        assert_eq!(sync_history_file(&conn, &seeded, Some(&seeded)).unwrap(), 1);
+    }
+}
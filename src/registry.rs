@@ -1,25 +1,93 @@
 use crate::database::db::now_secs;
 use crate::ops::apply::AlmanError;
 use crate::template::CommandTemplate;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DefinitionKind {
     Alias,
     Function,
+    /// Hand-written bodies for functions a single command can't express
+    /// (loops, conditionals, `local`); the template is unused.
+    Raw(RawBodies),
+    /// An exported environment variable; the value may reference other
+    /// variables as `$NAME` or `${NAME}`.
+    Env(String),
+}
+
+/// Shells that fall back to the `posix` body when they have none of their own.
+const POSIX_FALLBACK: &[&str] = &["bash", "zsh", "posix"];
+
+/// Function bodies of a raw definition, keyed by dialect.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawBodies(pub BTreeMap<String, String>);
+
+impl RawBodies {
+    /// The body to use in `dialect`: its own, else the `posix` one for shells
+    /// that can run it.
+    pub fn body_for(&self, dialect: &str) -> Option<&str> {
+        self.0
+            .get(dialect)
+            .or_else(|| POSIX_FALLBACK.contains(&dialect).then(|| self.0.get("posix")).flatten())
+            .map(String::as_str)
+    }
+
+    pub fn dialects(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
 }
 
 impl DefinitionKind {
-    fn as_str(&self) -> &'static str {
+    /// Alias when the template fits one (no slots, or a single trailing slot or
+    /// rest — the shell appends arguments to an alias anyway); otherwise a
+    /// function. Command sequences are always functions.
+    pub fn for_template(template: &CommandTemplate) -> Self {
+        if template.is_sequence() {
+            Self::Function
+        } else if template.is_zero_slot() || template.only_trailing_single_slot() {
+            Self::Alias
+        } else {
+            Self::Function
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
         match self {
             Self::Alias => "alias",
             Self::Function => "function",
+            Self::Raw(_) => "raw",
+            Self::Env(_) => "env",
         }
     }
-    fn from_str(s: &str) -> Option<Self> {
-        match s {
+
+    pub fn is_env(&self) -> bool {
+        matches!(self, Self::Env(_))
+    }
+
+    fn from_row(kind: &str, bodies_json: Option<&str>, value: Option<&str>) -> Option<Self> {
+        match kind {
             "alias" => Some(Self::Alias),
             "function" => Some(Self::Function),
+            "raw" => serde_json::from_str(bodies_json?).ok().map(Self::Raw),
+            "env" => value.map(|v| Self::Env(v.to_string())),
+            _ => None,
+        }
+    }
+
+    /// The variable's value to store in `value`; only env definitions have one.
+    fn value(&self) -> Option<&str> {
+        match self {
+            Self::Env(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The bodies to store in `bodies_json`; only raw definitions have them.
+    fn bodies_json(&self) -> Option<String> {
+        match self {
+            Self::Raw(bodies) => serde_json::to_string(bodies).ok(),
             _ => None,
         }
     }
@@ -39,15 +107,34 @@ pub fn upsert_definition(
     template: &CommandTemplate,
 ) -> Result<(), AlmanError> {
     conn.execute(
-        "INSERT INTO definitions (name, kind, template_json, created_at) VALUES (?1, ?2, ?3, ?4)
-         ON CONFLICT(name) DO UPDATE SET kind=excluded.kind, template_json=excluded.template_json",
-        params![name, kind.as_str(), template.to_json(), now_secs()],
+        "INSERT INTO definitions (name, kind, template_json, created_at, bodies_json, value)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(name) DO UPDATE SET kind=excluded.kind, template_json=excluded.template_json,
+                                         bodies_json=excluded.bodies_json, value=excluded.value",
+        params![name, kind.as_str(), template.to_json(), now_secs(), kind.bodies_json(), kind.value()],
     )?;
     Ok(())
 }
 
+/// Replace the body of `name`, keeping when it was created. False if there is no such definition.
+pub fn update_definition(
+    conn: &Connection,
+    name: &str,
+    kind: DefinitionKind,
+    template: &CommandTemplate,
+) -> Result<bool, AlmanError> {
+    let n = conn.execute(
+        "UPDATE definitions SET kind = ?2, template_json = ?3, bodies_json = ?4, value = ?5 WHERE name = ?1",
+        params![name, kind.as_str(), template.to_json(), kind.bodies_json(), kind.value()],
+    )?;
+    Ok(n > 0)
+}
+
 pub fn remove_definition(conn: &Connection, name: &str) -> Result<bool, AlmanError> {
     let n = conn.execute("DELETE FROM definitions WHERE name = ?1", params![name])?;
+    for table in ["slot_values", "definition_usage", "reminders"] {
+        conn.execute(&format!("DELETE FROM {table} WHERE definition = ?1"), params![name])?;
+    }
     Ok(n > 0)
 }
 
@@ -56,29 +143,129 @@ pub fn rename_definition(conn: &Connection, old: &str, new: &str) -> Result<bool
         "UPDATE definitions SET name = ?2 WHERE name = ?1",
         params![old, new],
     )?;
+    if n > 0 {
+        for table in ["slot_values", "definition_usage", "reminders"] {
+            conn.execute(&format!("DELETE FROM {table} WHERE definition = ?1"), params![new])?;
+            conn.execute(
+                &format!("UPDATE {table} SET definition = ?2 WHERE definition = ?1"),
+                params![old, new],
+            )?;
+        }
+    }
     Ok(n > 0)
 }
 
+/// The definition called `name`, if there is one.
+pub fn find_definition(conn: &Connection, name: &str) -> Result<Option<Definition>, AlmanError> {
+    let row = conn
+        .query_row(
+            "SELECT name, kind, template_json, bodies_json, value FROM definitions WHERE name = ?1",
+            params![name],
+            DefinitionRow::read,
+        )
+        .optional()?;
+    row.map(DefinitionRow::into_definition).transpose()
+}
+
 pub fn list_definitions(conn: &Connection) -> Result<Vec<Definition>, AlmanError> {
     let mut stmt = conn
-        .prepare("SELECT name, kind, template_json FROM definitions ORDER BY name")?;
-    let rows = stmt.query_map([], |row| {
-        let name: String = row.get(0)?;
-        let kind_str: String = row.get(1)?;
-        let tmpl_json: String = row.get(2)?;
-        Ok((name, kind_str, tmpl_json))
-    })?;
+        .prepare("SELECT name, kind, template_json, bodies_json, value FROM definitions ORDER BY name")?;
+    let rows = stmt.query_map([], DefinitionRow::read)?;
     let mut out = Vec::new();
     for row in rows {
-        let (name, kind_str, tmpl_json) = row?;
-        let kind = DefinitionKind::from_str(&kind_str).ok_or("bad kind")?;
-        let template = CommandTemplate::from_json(&tmpl_json).ok_or("bad template")?;
-        out.push(Definition { name, kind, template });
+        out.push(row?.into_definition()?);
+    }
+    Ok(out)
+}
+
+/// The columns of a `definitions` row, decoded once the statement is done.
+struct DefinitionRow {
+    name: String,
+    kind: String,
+    template_json: String,
+    bodies_json: Option<String>,
+    value: Option<String>,
+}
+
+impl DefinitionRow {
+    fn read(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get(0)?,
+            kind: row.get(1)?,
+            template_json: row.get(2)?,
+            bodies_json: row.get(3)?,
+            value: row.get(4)?,
+        })
+    }
+
+    fn into_definition(self) -> Result<Definition, AlmanError> {
+        let kind = DefinitionKind::from_row(&self.kind, self.bodies_json.as_deref(), self.value.as_deref()).ok_or("bad kind")?;
+        let template = CommandTemplate::from_json(&self.template_json).ok_or("bad template")?;
+        Ok(Definition { name: self.name, kind, template })
+    }
+}
+
+/// Remember values slot `slot` of definition `name` was seen with, each with
+/// the number of times it was seen; counts accumulate.
+pub fn record_slot_values(
+    conn: &Connection,
+    name: &str,
+    slot: u32,
+    values: &[(String, u32)],
+) -> Result<(), AlmanError> {
+    for (value, count) in values {
+        conn.execute(
+            "INSERT INTO slot_values (definition, slot, value, frequency) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(definition, slot, value) DO UPDATE SET frequency = frequency + excluded.frequency",
+            params![name, slot, value, count],
+        )?;
+    }
+    Ok(())
+}
+
+/// Count one run of `def` at `ts`. `args` are the words typed after its name;
+/// argument N is what slot N was bound to.
+pub fn record_definition_use(
+    conn: &Connection,
+    def: &Definition,
+    args: &[String],
+    ts: i64,
+) -> Result<(), AlmanError> {
+    conn.execute(
+        "INSERT INTO definition_usage (definition, uses, last_used) VALUES (?1, 1, ?2)
+         ON CONFLICT(definition) DO UPDATE SET uses = uses + 1, last_used = excluded.last_used",
+        params![def.name, ts],
+    )?;
+    for slot in 1..=def.template.max_slot() {
+        if let Some(value) = args.get(slot as usize - 1) {
+            record_slot_values(conn, &def.name, slot, &[(value.clone(), 1)])?;
+        }
+    }
+    Ok(())
+}
+
+/// `(uses, last used)` for every definition that has been run at least once.
+pub fn definition_usage(conn: &Connection) -> Result<HashMap<String, (i64, i64)>, AlmanError> {
+    let mut stmt = conn.prepare("SELECT definition, uses, last_used FROM definition_usage")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?))))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Values recorded for each slot of `name`, most frequent first.
+pub fn list_slot_values(conn: &Connection, name: &str) -> Result<BTreeMap<u32, Vec<String>>, AlmanError> {
+    let mut stmt = conn.prepare(
+        "SELECT slot, value FROM slot_values WHERE definition = ?1
+         ORDER BY slot, frequency DESC, value",
+    )?;
+    let rows = stmt.query_map(params![name], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)))?;
+    let mut out: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for row in rows {
+        let (slot, value) = row?;
+        out.entry(slot).or_default().push(value);
     }
     Ok(out)
 }
 
-#[cfg(test)]
 pub fn definition_exists(conn: &Connection, name: &str) -> Result<bool, AlmanError> {
     let n: i64 = conn.query_row(
         "SELECT COUNT(*) FROM definitions WHERE name = ?1",
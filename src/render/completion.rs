@@ -0,0 +1,232 @@
+use crate::registry::Definition;
+use crate::template::{SlotType, TemplatePart};
+use crate::tokenize::is_plain_word;
+use std::collections::BTreeMap;
+
+/// Most history values offered for one slot.
+const MAX_VALUES: usize = 50;
+
+/// What to offer for one argument of a generated alias or function.
+pub struct SlotCompletion {
+    /// 1-based argument position.
+    pub position: u32,
+    pub slot_type: SlotType,
+    /// Values seen in history, most frequent first. Only words that need no
+    /// quoting are kept so every shell can list them verbatim.
+    pub values: Vec<String>,
+    /// Command printing further candidates at completion time, one per line.
+    pub list_command: Option<String>,
+}
+
+/// Completions for each slot of `def`, given the values recorded for it.
+/// Slots with nothing to offer are left out.
+pub fn slot_completions(def: &Definition, history: &BTreeMap<u32, Vec<String>>) -> Vec<SlotCompletion> {
+    let runtime = if def.template.parts.contains(&TemplatePart::Literal("podman".into())) {
+        "podman"
+    } else {
+        "docker"
+    };
+    (1..=def.template.max_slot())
+        .filter_map(|n| {
+            let values: Vec<String> = history
+                .get(&n)
+                .into_iter()
+                .flatten()
+                .filter(|v| is_plain_word(v))
+                .take(MAX_VALUES)
+                .cloned()
+                .collect();
+            let slot_type = def.template.infer_slot_type(n, &values);
+            let list_command = match slot_type {
+                SlotType::GitBranch => {
+                    Some("git for-each-ref --format='%(refname:short)' refs/heads 2>/dev/null".to_string())
+                }
+                SlotType::Container => Some(format!("{runtime} ps --format '{{{{.Names}}}}' 2>/dev/null")),
+                _ => None,
+            };
+            let empty = values.is_empty() && matches!(slot_type, SlotType::Word | SlotType::Number);
+            (!empty).then_some(SlotCompletion { position: n, slot_type, values, list_command })
+        })
+        .collect()
+}
+
+pub trait CompletionRenderer: Send + Sync {
+    /// Completion setup for command `name`, or `None` when there is nothing to offer.
+    fn render_completion(&self, name: &str, slots: &[SlotCompletion]) -> Option<String>;
+}
+
+/// Completion function name for a definition; alias names may contain characters
+/// a function name cannot.
+fn function_name(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("_alman_complete_{safe}")
+}
+
+pub struct BashCompletion;
+
+impl BashCompletion {
+    fn candidates(slot: &SlotCompletion) -> String {
+        let values = slot.values.join(" ");
+        match (&slot.slot_type, &slot.list_command) {
+            (SlotType::Path, _) => r#"COMPREPLY=($(compgen -f -- "$cur"))"#.to_string(),
+            (SlotType::Hostname, _) => format!(r#"COMPREPLY=($(compgen -A hostname -W '{values}' -- "$cur"))"#),
+            (_, Some(list)) => format!(r#"COMPREPLY=($(compgen -W "$({list}) {values}" -- "$cur"))"#),
+            (_, None) => format!(r#"COMPREPLY=($(compgen -W '{values}' -- "$cur"))"#),
+        }
+    }
+}
+
+impl CompletionRenderer for BashCompletion {
+    fn render_completion(&self, name: &str, slots: &[SlotCompletion]) -> Option<String> {
+        // A single list of known words needs no function.
+        if let [slot] = slots {
+            if slot.position == 1
+                && slot.list_command.is_none()
+                && matches!(slot.slot_type, SlotType::Word | SlotType::Number)
+            {
+                return Some(format!("complete -W '{}' {}", slot.values.join(" "), name));
+            }
+        }
+        if slots.is_empty() {
+            return None;
+        }
+        let func = function_name(name);
+        let mut out = format!("{func}() {{\n    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    case \"$COMP_CWORD\" in\n");
+        for slot in slots {
+            out.push_str(&format!("        {}) {} ;;\n", slot.position, Self::candidates(slot)));
+        }
+        out.push_str(&format!("    esac\n}}\ncomplete -F {func} {name}"));
+        Some(out)
+    }
+}
+
+pub struct ZshCompletion;
+
+impl CompletionRenderer for ZshCompletion {
+    fn render_completion(&self, name: &str, slots: &[SlotCompletion]) -> Option<String> {
+        if slots.is_empty() {
+            return None;
+        }
+        let func = function_name(name);
+        let mut out = format!("{func}() {{\n    case $CURRENT in\n");
+        for slot in slots {
+            let values = slot.values.join(" ");
+            let action = match (&slot.slot_type, &slot.list_command) {
+                (SlotType::Path, _) => "_files".to_string(),
+                (SlotType::Hostname, _) => format!("_hosts; compadd -- {values}"),
+                (_, Some(list)) => format!("compadd -- $({list}) {values}"),
+                (_, None) => format!("compadd -- {values}"),
+            };
+            // $CURRENT counts the command word itself.
+            out.push_str(&format!("        {}) {} ;;\n", slot.position + 1, action));
+        }
+        out.push_str(&format!("    esac\n}}\n(( $+functions[compdef] )) && compdef {func} {name}"));
+        Some(out)
+    }
+}
+
+pub struct FishCompletion;
+
+impl FishCompletion {
+    fn quote(s: &str) -> String {
+        format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+impl CompletionRenderer for FishCompletion {
+    fn render_completion(&self, name: &str, slots: &[SlotCompletion]) -> Option<String> {
+        if slots.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = slots
+            .iter()
+            .map(|slot| {
+                let condition = Self::quote(&format!(
+                    "test (count (commandline -opc)) -eq {}",
+                    slot.position
+                ));
+                let mut words = slot.values.clone();
+                match (&slot.slot_type, &slot.list_command) {
+                    (SlotType::Path, _) => {
+                        return format!("complete -c {name} -F -n {condition}");
+                    }
+                    (SlotType::Hostname, _) => words.push("(__fish_print_hostnames)".to_string()),
+                    (_, Some(list)) => words.push(format!("({list})")),
+                    (_, None) => {}
+                }
+                format!(
+                    "complete -c {name} -f -n {condition} -a {}",
+                    Self::quote(&words.join(" "))
+                )
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::DefinitionKind;
+    use crate::template::CommandTemplate;
+
+    fn dex() -> Definition {
+        Definition {
+            name: "dex".into(),
+            kind: DefinitionKind::Function,
+            template: CommandTemplate::new(vec![
+                TemplatePart::Literal("docker".into()),
+                TemplatePart::Literal("exec".into()),
+                TemplatePart::Slot(1),
+                TemplatePart::Slot(2),
+            ]),
+        }
+    }
+
+    fn history() -> BTreeMap<u32, Vec<String>> {
+        BTreeMap::from([
+            (1, vec!["web".to_string(), "db".to_string()]),
+            (2, vec!["bash".to_string(), "sh -l".to_string()]),
+        ])
+    }
+
+    #[test]
+    fn slot_completions_type_and_filter_values() {
+        let slots = slot_completions(&dex(), &history());
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0].slot_type, SlotType::Container);
+        assert!(slots[0].list_command.as_deref().unwrap().starts_with("docker ps"));
+        assert_eq!(slots[1].values, vec!["bash"], "values needing quotes are dropped");
+    }
+
+    #[test]
+    fn renders_each_shell() {
+        let slots = slot_completions(&dex(), &history());
+
+        let bash = BashCompletion.render_completion("dex", &slots).unwrap();
+        assert!(bash.contains(r#"1) COMPREPLY=($(compgen -W "$(docker ps --format '{{.Names}}' 2>/dev/null) web db" -- "$cur")) ;;"#), "{bash}");
+        assert!(bash.ends_with("complete -F _alman_complete_dex dex"), "{bash}");
+
+        let zsh = ZshCompletion.render_completion("dex", &slots).unwrap();
+        assert!(zsh.contains("3) compadd -- bash ;;"), "{zsh}");
+        assert!(zsh.ends_with("compdef _alman_complete_dex dex"), "{zsh}");
+
+        let fish = FishCompletion.render_completion("dex", &slots).unwrap();
+        assert!(
+            fish.contains(r#"complete -c dex -f -n 'test (count (commandline -opc)) -eq 2' -a 'bash'"#),
+            "{fish}"
+        );
+
+        let single = [SlotCompletion {
+            position: 1,
+            slot_type: SlotType::Word,
+            values: vec!["a".into(), "b".into()],
+            list_command: None,
+        }];
+        assert_eq!(BashCompletion.render_completion("x", &single).unwrap(), "complete -W 'a b' x");
+        assert!(BashCompletion.render_completion("x", &[]).is_none());
+    }
+}
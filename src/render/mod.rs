@@ -1,47 +1,131 @@
+pub mod completion;
+mod nu;
+mod pwsh;
+mod tcsh;
+
+use crate::ast::ShellOp;
 use crate::registry::Definition;
 use crate::template::{CommandTemplate, TemplatePart};
 
+pub use nu::NuRenderer;
+pub use pwsh::PwshRenderer;
+pub use tcsh::TcshRenderer;
+
 pub trait ShellRenderer: Send + Sync {
     fn slot_ref(&self, n: u32) -> String;
+    /// Reference to a slot that falls back to `default` when not passed. Shells
+    /// without an inline form fill the default in `render_prologue` instead.
+    fn default_slot_ref(&self, n: u32, _default: &str) -> String {
+        self.slot_ref(n)
+    }
+    /// All arguments after the first `after` positional ones.
+    fn rest_ref(&self, after: u32) -> String;
     fn quote_literal(&self, tok: &str) -> String;
+    /// An operator, unquoted, in this shell's syntax.
+    fn render_op(&self, op: &ShellOp) -> String {
+        op.as_str().to_string()
+    }
     fn render_alias(&self, name: &str, command: &str) -> String;
-    fn render_function(&self, name: &str, body: &str) -> String;
+    fn render_function(&self, name: &str, statements: &[String]) -> String;
+    /// Statements that run before the function body, e.g. the usage check.
+    fn render_prologue(&self, name: &str, t: &CommandTemplate) -> Vec<String>;
+
+    /// A raw definition's hand-written body wrapped as a function.
+    fn render_raw(&self, name: &str, body: &str) -> String;
+    /// An exported environment variable; references in `value` still expand.
+    fn render_env(&self, name: &str, value: &str) -> String;
+    /// The raw-body dialect this renderer writes when no shell is named.
+    fn dialect(&self) -> &'static str {
+        "posix"
+    }
+    /// Whether every operator in `t` can be written in this shell.
+    fn supports(&self, _t: &CommandTemplate) -> bool {
+        true
+    }
+    /// Whether a raw definition's hand-written `body` can be wrapped for this shell.
+    fn supports_raw(&self, _body: &str) -> bool {
+        true
+    }
+    /// Whether `t` can be written for the shell `dialect`, for renderers that
+    /// serve several shells with different features.
+    fn supports_in(&self, t: &CommandTemplate, _dialect: &str) -> bool {
+        self.supports(t)
+    }
+
+    /// `d` for the shell `dialect`; `None` when it is raw and has no body that
+    /// shell can run, or needs something the shell lacks.
+    fn render_definition_in(&self, d: &Definition, dialect: &str) -> Option<String> {
+        use crate::registry::DefinitionKind;
+        match &d.kind {
+            DefinitionKind::Raw(bodies) => {
+                bodies
+                    .body_for(dialect)
+                    .map(str::trim_end)
+                    .filter(|body| self.supports_raw(body))
+                    .map(|body| self.render_raw(&d.name, body))
+            }
+            DefinitionKind::Alias | DefinitionKind::Function if !self.supports_in(&d.template, dialect) => None,
+            _ => Some(self.render_definition(d)),
+        }
+    }
+
+    /// One line describing what `d` runs, for listings.
+    fn render_summary(&self, d: &Definition) -> String {
+        use crate::registry::DefinitionKind;
+        match &d.kind {
+            DefinitionKind::Raw(bodies) => format!("<raw: {}>", bodies.dialects().join(", ")),
+            DefinitionKind::Env(value) => value.clone(),
+            _ => self.render_template_body(&d.template),
+        }
+    }
 
     fn render_definition(&self, d: &Definition) -> String {
         use crate::registry::DefinitionKind;
         match d.kind {
-            DefinitionKind::Alias => {
-                // zero-slot or trailing-single-slot → alias.
-                // The command text is built raw here; `render_alias` quotes it once.
-                let command = self.render_alias_command(&d.template);
-                self.render_alias(&d.name, &command)
-            }
-            DefinitionKind::Function => {
-                let body = self.render_template_body(&d.template);
-                self.render_function(&d.name, &body)
+            DefinitionKind::Alias | DefinitionKind::Function if !self.supports(&d.template) => {
+                format!("# alman: {} cannot be written in {}", d.name, self.dialect())
             }
+            DefinitionKind::Alias => self.render_alias_definition(&d.name, &d.template),
+            DefinitionKind::Function => self.render_function_definition(&d.name, &d.template),
+            DefinitionKind::Raw(ref bodies) => self.render_definition_in(d, self.dialect()).unwrap_or_else(|| {
+                if bodies.body_for(self.dialect()).is_some() {
+                    format!("# alman: {} cannot be written in {}", d.name, self.dialect())
+                } else {
+                    format!("# alman: {} has no {} body", d.name, self.dialect())
+                }
+            }),
+            DefinitionKind::Env(ref value) => self.render_env(&d.name, value),
         }
     }
 
+    /// A zero-slot or trailing-single-slot template as an alias. The command
+    /// text is built raw here; `render_alias` quotes it once.
+    fn render_alias_definition(&self, name: &str, t: &CommandTemplate) -> String {
+        self.render_alias(name, &self.render_alias_command(t))
+    }
+
+    fn render_function_definition(&self, name: &str, t: &CommandTemplate) -> String {
+        let mut statements = self.render_prologue(name, t);
+        statements.push(self.render_template_body(t));
+        self.render_function(name, &statements)
+    }
+
     /// Build the raw (unquoted) command text for an alias body. Slots are kept
     /// as raw positional refs so a trailing-single-slot alias still works when
-    /// the shell appends arguments after the alias expansion.
+    /// the shell appends arguments after the alias expansion. A trailing `Rest`
+    /// is dropped for the same reason.
     fn render_alias_command(&self, t: &CommandTemplate) -> String {
         t.parts
             .iter()
-            .enumerate()
-            .map(|(i, part)| {
-                let s = match part {
-                    TemplatePart::Literal(s) => s.clone(),
-                    TemplatePart::Slot(n) => self.slot_ref(*n),
-                };
-                if i == 0 {
-                    s
-                } else {
-                    format!(" {}", s)
-                }
+            .filter_map(|part| match part {
+                TemplatePart::Literal(s) => Some(s.clone()),
+                TemplatePart::Slot(n) => Some(self.slot_ref(*n)),
+                TemplatePart::AndThen => Some("&&".to_string()),
+                TemplatePart::Rest => None,
+                TemplatePart::Op(op) => Some(self.render_op(op)),
             })
-            .collect()
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     fn render_template_body(&self, t: &CommandTemplate) -> String {
@@ -51,7 +135,13 @@ pub trait ShellRenderer: Send + Sync {
             .map(|(i, part)| {
                 let s = match part {
                     TemplatePart::Literal(s) => self.quote_literal(s),
-                    TemplatePart::Slot(n) => self.slot_ref(*n),
+                    TemplatePart::Slot(n) => match t.slot_default(*n) {
+                        Some(default) => self.default_slot_ref(*n, default),
+                        None => self.slot_ref(*n),
+                    },
+                    TemplatePart::AndThen => "&&".to_string(),
+                    TemplatePart::Rest => self.rest_ref(t.max_slot()),
+                    TemplatePart::Op(op) => self.render_op(op),
                 };
                 if i == 0 {
                     s
@@ -63,12 +153,123 @@ pub trait ShellRenderer: Send + Sync {
     }
 }
 
+/// A piece of an environment variable's value.
+#[derive(Debug, PartialEq)]
+pub enum ValuePart {
+    /// Text kept exactly as written.
+    Text(String),
+    /// A reference to another variable, expanded by the shell.
+    Var(String),
+}
+
+/// Split `value` into text and `$NAME` / `${NAME}` references. A `~` at the
+/// start or after a `:` stands for `$HOME`, and `\$` is a literal dollar sign.
+pub fn value_parts(value: &str) -> Vec<ValuePart> {
+    let chars: Vec<char> = value.chars().collect();
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let mut var = None;
+        if c == '\\' && chars.get(i + 1) == Some(&'$') {
+            text.push('$');
+            i += 2;
+            continue;
+        } else if c == '~'
+            && (i == 0 || chars[i - 1] == ':')
+            && chars.get(i + 1).is_none_or(|&next| next == '/' || next == ':')
+        {
+            var = Some(("HOME".to_string(), 1));
+        } else if c == '$' {
+            if chars.get(i + 1) == Some(&'{') {
+                if let Some(len) = chars[i + 2..].iter().position(|&c| c == '}') {
+                    let name: String = chars[i + 2..i + 2 + len].iter().collect();
+                    if !name.is_empty() && name.chars().all(is_name_char) {
+                        var = Some((name, len + 3));
+                    }
+                }
+            } else if chars.get(i + 1).is_some_and(|&c| c.is_ascii_alphabetic() || c == '_') {
+                let len = chars[i + 1..].iter().take_while(|&&c| is_name_char(c)).count();
+                var = Some((chars[i + 1..i + 1 + len].iter().collect(), len + 1));
+            }
+        }
+        match var {
+            Some((name, len)) => {
+                if !text.is_empty() {
+                    parts.push(ValuePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(ValuePart::Var(name));
+                i += len;
+            }
+            None => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !text.is_empty() || parts.is_empty() {
+        parts.push(ValuePart::Text(text));
+    }
+    parts
+}
+
+/// A template's parts, with a plain alias's command text split into words, for
+/// shells whose quoting differs word by word.
+pub(crate) fn word_parts(t: &CommandTemplate) -> Vec<TemplatePart> {
+    match t.parts.as_slice() {
+        [TemplatePart::Literal(text)] => crate::defaults::default_tokenizer()
+            .lex(text)
+            .iter()
+            .map(TemplatePart::from_token)
+            .collect(),
+        parts => parts.to_vec(),
+    }
+}
+
+/// Join `parts` rendered by `word`, which is also told whether the part stands
+/// where a command name goes.
+pub(crate) fn render_words(parts: &[TemplatePart], mut word: impl FnMut(&TemplatePart, bool) -> String) -> String {
+    let mut command_position = true;
+    let mut words = Vec::new();
+    for part in parts {
+        words.push(word(part, command_position));
+        command_position = matches!(
+            part,
+            TemplatePart::AndThen
+                | TemplatePart::Op(ShellOp::Pipe | ShellOp::And | ShellOp::Or | ShellOp::Seq | ShellOp::SubshellOpen)
+        );
+    }
+    words.join(" ")
+}
+
 pub struct PosixRenderer;
 
 impl ShellRenderer for PosixRenderer {
     fn slot_ref(&self, n: u32) -> String {
         format!("\"${}\"", n)
     }
+    fn default_slot_ref(&self, n: u32, default: &str) -> String {
+        // Inside "${1:-…}" the default is itself double-quoted text.
+        let mut escaped = String::with_capacity(default.len());
+        for c in default.chars() {
+            if matches!(c, '\\' | '"' | '$' | '`' | '}') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        format!("\"${{{}:-{}}}\"", n, escaped)
+    }
+    fn rest_ref(&self, after: u32) -> String {
+        // `${@:N}` is understood by bash, zsh and ksh93 but not dash, so
+        // `supports_in` keeps a rest after slots out of the posix dialect.
+        if after == 0 {
+            "\"$@\"".to_string()
+        } else {
+            format!("\"${{@:{}}}\"", after + 1)
+        }
+    }
     fn quote_literal(&self, tok: &str) -> String {
         // single-quote with embedded ' escaped as '\''
         format!("'{}'", tok.replace('\'', r"'\''"))
@@ -76,8 +277,40 @@ impl ShellRenderer for PosixRenderer {
     fn render_alias(&self, name: &str, command: &str) -> String {
         format!("alias {}={}", name, self.quote_literal(command))
     }
-    fn render_function(&self, name: &str, body: &str) -> String {
-        format!("{}() {{ {}; }}", name, body)
+    fn render_function(&self, name: &str, statements: &[String]) -> String {
+        let body = statements.join("; ");
+        // A trailing `;` or `&` already ends the command; `;;` and `&;` would be
+        // syntax errors.
+        let body = body.strip_suffix(" ;").unwrap_or(&body);
+        let end = if body.ends_with(" &") { " }" } else { "; }" };
+        format!("{}() {{ {}{}", name, body, end)
+    }
+    fn render_raw(&self, name: &str, body: &str) -> String {
+        format!("{}() {{\n{}\n}}", name, body)
+    }
+    fn render_env(&self, name: &str, value: &str) -> String {
+        let quoted: String = value_parts(value)
+            .iter()
+            .map(|part| match part {
+                ValuePart::Text(text) => self.quote_literal(text),
+                ValuePart::Var(var) => format!("\"${}\"", var),
+            })
+            .collect();
+        format!("export {}={}", name, quoted)
+    }
+    fn supports_in(&self, t: &CommandTemplate, dialect: &str) -> bool {
+        dialect != "posix" || t.max_slot() == 0 || !t.parts.contains(&TemplatePart::Rest)
+    }
+    fn render_prologue(&self, name: &str, t: &CommandTemplate) -> Vec<String> {
+        let required = t.required_args();
+        if required == 0 {
+            return Vec::new();
+        }
+        vec![format!(
+            "[ \"$#\" -ge {} ] || {{ echo {} >&2; return 2; }}",
+            required,
+            self.quote_literal(&t.usage(name))
+        )]
     }
 }
 
@@ -87,45 +320,106 @@ impl ShellRenderer for FishRenderer {
     fn slot_ref(&self, n: u32) -> String {
         format!("$argv[{}]", n)
     }
+    fn rest_ref(&self, after: u32) -> String {
+        if after == 0 {
+            "$argv".to_string()
+        } else {
+            format!("$argv[{}..-1]", after + 1)
+        }
+    }
     fn quote_literal(&self, tok: &str) -> String {
         // fish uses double-quotes; escape " and \
         format!("\"{}\"", tok.replace('\\', "\\\\").replace('"', "\\\""))
     }
+    fn render_op(&self, op: &ShellOp) -> String {
+        // fish has no subshells; a block is the closest grouping it offers,
+        // though `cd` and variables inside it are not undone afterwards.
+        match op {
+            ShellOp::SubshellOpen => "begin;".to_string(),
+            ShellOp::SubshellClose => "; end".to_string(),
+            _ => op.as_str().to_string(),
+        }
+    }
     fn render_alias(&self, name: &str, command: &str) -> String {
         format!("alias {} {}", name, self.quote_literal(command))
     }
-    fn render_function(&self, name: &str, body: &str) -> String {
-        format!("function {}\n    {}\nend", name, body)
+    fn render_function(&self, name: &str, statements: &[String]) -> String {
+        format!("function {}\n    {}\nend", name, statements.join("\n    "))
+    }
+    fn render_raw(&self, name: &str, body: &str) -> String {
+        format!("function {}\n{}\nend", name, body)
+    }
+    fn render_env(&self, name: &str, value: &str) -> String {
+        // Single quotes keep text literal; fish only escapes \ and ' inside them.
+        let quoted: String = value_parts(value)
+            .iter()
+            .map(|part| match part {
+                ValuePart::Text(text) => format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'")),
+                ValuePart::Var(var) => format!("\"${}\"", var),
+            })
+            .collect();
+        format!("set -gx {} {}", name, quoted)
+    }
+    fn dialect(&self) -> &'static str {
+        "fish"
+    }
+    fn render_prologue(&self, name: &str, t: &CommandTemplate) -> Vec<String> {
+        let required = t.required_args();
+        let mut statements = Vec::new();
+        if required > 0 {
+            statements.push(format!(
+                "if test (count $argv) -lt {}; echo {} >&2; return 2; end",
+                required,
+                self.quote_literal(&t.usage(name))
+            ));
+        }
+        // Defaults only follow required slots, so each `set` appends one element.
+        for n in required + 1..=t.max_slot() {
+            if let Some(default) = t.slot_default(n) {
+                statements.push(format!(
+                    "set -q argv[{n}]; or set argv[{n}] {}",
+                    self.quote_literal(default)
+                ));
+            }
+        }
+        statements
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::registry::{Definition, DefinitionKind};
+    use crate::registry::{Definition, DefinitionKind, RawBodies};
+    use crate::template::SlotSpec;
+    use crate::tokenize::{ShellTokenizer, Tokenizer};
 
-    fn interior_def() -> Definition {
+    pub(super) fn interior_def() -> Definition {
         Definition {
             name: "dex".into(),
             kind: DefinitionKind::Function,
-            template: CommandTemplate {
-                parts: vec![
-                    TemplatePart::Literal("docker".into()),
-                    TemplatePart::Literal("exec".into()),
-                    TemplatePart::Slot(1),
-                    TemplatePart::Literal("bash".into()),
-                ],
-            },
+            template: CommandTemplate::new(vec![
+                TemplatePart::Literal("docker".into()),
+                TemplatePart::Literal("exec".into()),
+                TemplatePart::Slot(1),
+                TemplatePart::Literal("bash".into()),
+            ]),
         }
     }
 
-    fn zero_def() -> Definition {
+    /// The variable `KUBECONFIG` exported with `value`.
+    pub(super) fn env(value: &str) -> Definition {
+        Definition {
+            name: "KUBECONFIG".into(),
+            kind: DefinitionKind::Env(value.into()),
+            template: CommandTemplate::default(),
+        }
+    }
+
+    pub(super) fn zero_def() -> Definition {
         Definition {
             name: "gs".into(),
             kind: DefinitionKind::Alias,
-            template: CommandTemplate {
-                parts: vec![TemplatePart::Literal("git status".into())],
-            },
+            template: CommandTemplate::new(vec![TemplatePart::Literal("git status".into())]),
         }
     }
 
@@ -143,6 +437,96 @@ mod tests {
         assert_eq!(out, "alias gs='git status'");
     }
 
+    #[test]
+    fn sequence_renders_unquoted_and_then() {
+        let def = Definition {
+            name: "gacp".into(),
+            kind: DefinitionKind::Function,
+            template: CommandTemplate::new(vec![
+                TemplatePart::Literal("git".into()),
+                TemplatePart::Literal("commit".into()),
+                TemplatePart::Slot(1),
+                TemplatePart::AndThen,
+                TemplatePart::Literal("git".into()),
+                TemplatePart::Literal("push".into()),
+            ]),
+        };
+        let out = PosixRenderer.render_definition(&def);
+        assert_eq!(
+            out,
+            "gacp() { [ \"$#\" -ge 1 ] || { echo 'usage: gacp <arg1>' >&2; return 2; }; \
+             'git' 'commit' \"$1\" && 'git' 'push'; }"
+        );
+    }
+
+    #[test]
+    fn named_slots_render_usage_check_and_defaults() {
+        let mut def = interior_def();
+        def.template.parts.push(TemplatePart::Slot(2));
+        def.template.slots.insert(1, SlotSpec::parse("container").unwrap());
+        def.template.slots.insert(2, SlotSpec::parse("shell=sh").unwrap());
+
+        assert_eq!(
+            PosixRenderer.render_definition(&def),
+            "dex() { [ \"$#\" -ge 1 ] || { echo 'usage: dex <container> [shell=sh]' >&2; return 2; }; \
+             'docker' 'exec' \"$1\" 'bash' \"${2:-sh}\"; }"
+        );
+        assert_eq!(
+            FishRenderer.render_definition(&def),
+            "function dex\n    \
+             if test (count $argv) -lt 1; echo \"usage: dex <container> [shell=sh]\" >&2; return 2; end\n    \
+             set -q argv[2]; or set argv[2] \"sh\"\n    \
+             \"docker\" \"exec\" $argv[1] \"bash\" $argv[2]\nend"
+        );
+    }
+
+    #[test]
+    fn rest_renders_all_arguments() {
+        let def = Definition {
+            name: "rgf".into(),
+            kind: DefinitionKind::Function,
+            template: CommandTemplate::new(vec![
+                TemplatePart::Literal("rg".into()),
+                TemplatePart::Literal("foo".into()),
+                TemplatePart::Rest,
+            ]),
+        };
+        assert_eq!(PosixRenderer.render_definition(&def), "rgf() { 'rg' 'foo' \"$@\"; }");
+        assert!(FishRenderer.render_definition(&def).contains("\"foo\" $argv\n"));
+
+        let alias = Definition { kind: DefinitionKind::Alias, ..def };
+        assert_eq!(PosixRenderer.render_definition(&alias), "alias rgf='rg foo'");
+
+        // Arguments after a slot need `${@:N}`, which dash lacks.
+        let after_slot = Definition {
+            name: "xs".into(),
+            kind: DefinitionKind::Function,
+            template: CommandTemplate::parse("x {1} {...}").unwrap(),
+        };
+        assert!(PosixRenderer.render_definition_in(&after_slot, "bash").unwrap().contains("\"${@:2}\""));
+        assert_eq!(PosixRenderer.render_definition_in(&after_slot, "posix"), None);
+    }
+
+    #[test]
+    fn operators_render_unquoted() {
+        let tokens = ShellTokenizer.lex("(cd web && make) 2>&1 | grep err > log &");
+        let mut parts: Vec<TemplatePart> = tokens.iter().map(TemplatePart::from_token).collect();
+        parts[9] = TemplatePart::Slot(1);
+        let def = Definition {
+            name: "mk".into(),
+            kind: DefinitionKind::Function,
+            template: CommandTemplate::new(parts),
+        };
+        assert_eq!(
+            PosixRenderer.render_definition(&def),
+            "mk() { [ \"$#\" -ge 1 ] || { echo 'usage: mk <arg1>' >&2; return 2; }; \
+             ( 'cd' 'web' && 'make' ) 2>&1 | 'grep' \"$1\" > 'log' & }"
+        );
+        assert!(FishRenderer
+            .render_definition(&def)
+            .ends_with("begin; \"cd\" \"web\" && \"make\" ; end 2>&1 | \"grep\" $argv[1] > \"log\" &\nend"));
+    }
+
     #[test]
     fn fish_uses_argv_and_function_end() {
         let out = FishRenderer.render_definition(&interior_def());
@@ -150,4 +534,39 @@ mod tests {
         assert!(out.starts_with("function dex"), "got {out}");
         assert!(out.ends_with("end"), "got {out}");
     }
+
+    #[test]
+    fn raw_bodies_render_per_dialect() {
+        let bodies = RawBodies(
+            [("posix", "for d in \"$@\"; do\n  mkdir -p \"$d\"\ndone\n"), ("zsh", "mkdir -p -- \"$@\"")]
+                .into_iter()
+                .map(|(d, b)| (d.to_string(), b.to_string()))
+                .collect(),
+        );
+        let def = Definition { name: "mk".into(), kind: DefinitionKind::Raw(bodies), template: CommandTemplate::default() };
+
+        assert_eq!(
+            PosixRenderer.render_definition_in(&def, "bash").as_deref(),
+            Some("mk() {\nfor d in \"$@\"; do\n  mkdir -p \"$d\"\ndone\n}")
+        );
+        assert_eq!(PosixRenderer.render_definition_in(&def, "zsh").as_deref(), Some("mk() {\nmkdir -p -- \"$@\"\n}"));
+        assert_eq!(FishRenderer.render_definition_in(&def, "fish"), None);
+        assert_eq!(FishRenderer.render_definition(&def), "# alman: mk has no fish body");
+        assert_eq!(PosixRenderer.render_summary(&def), "<raw: posix, zsh>");
+    }
+
+    #[test]
+    fn env_values_keep_references_and_quote_text() {
+        assert_eq!(PosixRenderer.render_definition(&env("it's")), "export KUBECONFIG='it'\\''s'");
+        assert_eq!(FishRenderer.render_definition(&env("it's")), "set -gx KUBECONFIG 'it\\'s'");
+        assert_eq!(
+            PosixRenderer.render_definition(&env("~/.kube/${CLUSTER}.yaml:$HOME/x $5")),
+            "export KUBECONFIG=\"$HOME\"'/.kube/'\"$CLUSTER\"'.yaml:'\"$HOME\"'/x $5'"
+        );
+        assert_eq!(
+            FishRenderer.render_definition(&env("\\$HOME:~")),
+            "set -gx KUBECONFIG '$HOME:'\"$HOME\""
+        );
+        assert_eq!(PosixRenderer.render_definition(&env("")), "export KUBECONFIG=''");
+    }
 }
@@ -0,0 +1,264 @@
+use super::{render_words, value_parts, word_parts, ShellRenderer, ValuePart};
+use crate::ast::ShellOp;
+use crate::template::{CommandTemplate, TemplatePart};
+use std::collections::BTreeSet;
+
+/// Nushell commands that have no program of the same name, so a POSIX command
+/// line using them still means the builtin.
+const NU_BUILTINS: &[&str] = &[
+    "alias", "break", "cd", "const", "continue", "def", "do", "exec", "exit", "for", "hide",
+    "if", "let", "load-env", "loop", "mut", "overlay", "return", "source", "source-env", "try",
+    "use", "while", "with-env",
+];
+
+/// Nushell. Its aliases hold one command and take arguments only at the end,
+/// so anything else becomes a `def` with named parameters.
+#[derive(Default)]
+pub struct NuRenderer {
+    /// Names of the other saved definitions, which are nushell custom commands
+    /// rather than programs on PATH.
+    pub commands: BTreeSet<String>,
+}
+
+impl NuRenderer {
+    /// `op` in nushell syntax; `None` for `||`, `&` and redirections it lacks.
+    fn op(op: &ShellOp) -> Option<&'static str> {
+        match op {
+            ShellOp::Pipe => Some("|"),
+            // A block stops at the first command that fails.
+            ShellOp::And | ShellOp::Seq => Some(";"),
+            // `do` keeps `cd` and variables from leaking out, like a subshell.
+            ShellOp::SubshellOpen => Some("do {"),
+            ShellOp::SubshellClose => Some("}"),
+            ShellOp::Redirect(r) => match r.as_str() {
+                ">" | "1>" => Some("o>"),
+                ">>" | "1>>" => Some("o>>"),
+                "2>" => Some("e>"),
+                "2>>" => Some("e>>"),
+                "&>" => Some("o+e>"),
+                _ => None,
+            },
+            ShellOp::Or | ShellOp::Background => None,
+        }
+    }
+
+    /// Parameter for slot `n`: its name, made a valid nushell identifier.
+    fn param(t: &CommandTemplate, n: u32) -> String {
+        let name: String = t
+            .slot_name(n)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("_{name}")
+        } else {
+            name
+        }
+    }
+
+    /// The rest parameter, kept clear of the slot names.
+    fn rest_param(t: &CommandTemplate) -> &'static str {
+        if (1..=t.max_slot()).any(|n| Self::param(t, n) == "rest") {
+            "rest_args"
+        } else {
+            "rest"
+        }
+    }
+
+    /// A string literal; nushell's single quotes have no escapes at all.
+    fn string(text: &str) -> String {
+        if text.contains('\'') {
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            format!("'{}'", text)
+        }
+    }
+
+    fn words(&self, parts: &[TemplatePart], t: &CommandTemplate) -> String {
+        render_words(parts, |part, command_position| match part {
+            TemplatePart::Literal(s) => {
+                let quoted = self.quote_literal(s);
+                // Templates are POSIX command lines, so `ls` or `ps` means the
+                // program, not nushell's command of that name; `^` runs the
+                // program, and also keeps a quoted command from being a string.
+                let internal = NU_BUILTINS.contains(&s.as_str()) || self.commands.contains(s);
+                if command_position && !internal {
+                    format!("^{quoted}")
+                } else {
+                    quoted
+                }
+            }
+            TemplatePart::Slot(n) => format!("${}", Self::param(t, *n)),
+            TemplatePart::Rest => format!("...${}", Self::rest_param(t)),
+            TemplatePart::AndThen => ";".to_string(),
+            TemplatePart::Op(op) => self.render_op(op),
+        })
+    }
+}
+
+impl ShellRenderer for NuRenderer {
+    // The parameter names of a template without named slots; bodies are
+    // rendered through `words`, which knows the template's own names.
+    fn slot_ref(&self, n: u32) -> String {
+        format!("${}", Self::param(&CommandTemplate::default(), n))
+    }
+    fn rest_ref(&self, _after: u32) -> String {
+        format!("...${}", Self::rest_param(&CommandTemplate::default()))
+    }
+    fn quote_literal(&self, tok: &str) -> String {
+        // Bare words that could parse as numbers, dates, sizes or globs are quoted.
+        let bare = tok.starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '_' | '/'))
+            || (tok.starts_with('-') && tok[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '-'));
+        if bare && tok.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c)) {
+            tok.to_string()
+        } else {
+            Self::string(tok)
+        }
+    }
+    fn render_op(&self, op: &ShellOp) -> String {
+        Self::op(op).unwrap_or(op.as_str()).to_string()
+    }
+    fn supports(&self, t: &CommandTemplate) -> bool {
+        word_parts(t).iter().all(|part| match part {
+            TemplatePart::Op(op) => Self::op(op).is_some(),
+            _ => true,
+        })
+    }
+    fn render_alias(&self, name: &str, command: &str) -> String {
+        format!("alias {} = {}", name, command)
+    }
+    fn render_function(&self, name: &str, statements: &[String]) -> String {
+        format!("def --env {} [] {{ {} }}", name, statements.join("; "))
+    }
+    fn render_prologue(&self, _name: &str, _t: &CommandTemplate) -> Vec<String> {
+        // Nushell checks the parameter list itself.
+        Vec::new()
+    }
+    fn render_raw(&self, name: &str, body: &str) -> String {
+        format!("def --env --wrapped {} [...args] {{\n{}\n}}", name, body)
+    }
+    fn render_env(&self, name: &str, value: &str) -> String {
+        let parts = value_parts(value);
+        if let [ValuePart::Text(text)] = parts.as_slice() {
+            return format!("$env.{} = {}", name, Self::string(text));
+        }
+        // Lists such as PATH are joined back into one string.
+        let interpolated: String = parts
+            .iter()
+            .map(|part| match part {
+                ValuePart::Text(text) => text.replace('\\', "\\\\").replace('"', "\\\"").replace('(', "\\("),
+                ValuePart::Var(var) => format!("([$env.{}?] | flatten | compact | str join (char esep))", var),
+            })
+            .collect();
+        format!("$env.{} = $\"{}\"", name, interpolated)
+    }
+    fn dialect(&self) -> &'static str {
+        "nu"
+    }
+    fn render_alias_command(&self, t: &CommandTemplate) -> String {
+        self.words(&word_parts(t), t)
+    }
+    fn render_template_body(&self, t: &CommandTemplate) -> String {
+        self.words(&word_parts(t), t)
+    }
+    fn render_alias_definition(&self, name: &str, t: &CommandTemplate) -> String {
+        // Arguments typed after the alias land where its trailing slot or rest is.
+        let words: Vec<TemplatePart> = word_parts(t)
+            .into_iter()
+            .filter(|p| !matches!(p, TemplatePart::Slot(_) | TemplatePart::Rest))
+            .collect();
+        let body = self.words(&words, t);
+        if words.iter().any(|p| matches!(p, TemplatePart::Op(_) | TemplatePart::AndThen)) {
+            format!("def --env --wrapped {} [...rest] {{ {} ...$rest }}", name, body)
+        } else {
+            self.render_alias(name, &body)
+        }
+    }
+    fn render_function_definition(&self, name: &str, t: &CommandTemplate) -> String {
+        let required = t.required_args();
+        let mut params: Vec<String> = (1..=t.max_slot())
+            .map(|n| match t.slot_default(n) {
+                Some(default) if n > required => format!("{} = {}", Self::param(t, n), Self::string(default)),
+                _ => Self::param(t, n),
+            })
+            .collect();
+        // `--wrapped` passes flags through to the rest instead of rejecting them.
+        let wrapped = t.parts.contains(&TemplatePart::Rest);
+        if wrapped {
+            params.push(format!("...{}", Self::rest_param(t)));
+        }
+        // `--env` keeps a `cd` or variable set in the body, as a shell function would.
+        format!(
+            "def --env {}{} [{}] {{ {} }}",
+            if wrapped { "--wrapped " } else { "" },
+            name,
+            params.join(", "),
+            self.words(&word_parts(t), t)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::Definition;
+    use crate::render::tests::{env, interior_def, zero_def};
+    use crate::template::SlotSpec;
+
+    #[test]
+    fn nu_renders_aliases_defs_and_variables() {
+        assert_eq!(NuRenderer::default().render_definition(&zero_def()), "alias gs = ^git status");
+        let piped = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("ps aux | grep 'my app' > out".into())]),
+            ..zero_def()
+        };
+        assert_eq!(
+            NuRenderer::default().render_definition(&piped),
+            "def --env --wrapped gs [...rest] { ^ps aux | ^grep 'my app' o> out ...$rest }"
+        );
+
+        let mut def = interior_def();
+        def.template.parts.push(TemplatePart::Rest);
+        def.template.slots.insert(1, SlotSpec::parse("the-box").unwrap());
+        assert_eq!(
+            NuRenderer::default().render_definition(&def),
+            "def --env --wrapped dex [the_box, ...rest] { ^docker exec $the_box bash ...$rest }"
+        );
+        def.template.parts.pop();
+        def.template.parts.push(TemplatePart::Slot(2));
+        def.template.slots.insert(2, SlotSpec::parse("shell=sh").unwrap());
+        assert_eq!(NuRenderer::default().render_definition(&def), "def --env dex [the_box, shell = 'sh'] { ^docker exec $the_box bash $shell }");
+        // Listings name the same parameters the `def` declares.
+        assert_eq!(NuRenderer::default().render_summary(&def), "^docker exec $the_box bash $shell");
+
+        let unsupported = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("make || say failed".into())]),
+            ..zero_def()
+        };
+        assert_eq!(NuRenderer::default().render_definition_in(&unsupported, "nu"), None);
+        assert_eq!(NuRenderer::default().render_definition(&unsupported), "# alman: gs cannot be written in nu");
+        let quoted = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("'my tool' 10 -5 it\\'s".into())]),
+            ..zero_def()
+        };
+        assert_eq!(NuRenderer::default().render_definition(&quoted), "alias gs = ^'my tool' '10' '-5' \"it's\"");
+
+        // Builtins without a program, and other saved definitions, stay bare.
+        let chained = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("cd src && ll -a".into())]),
+            ..zero_def()
+        };
+        let renderer = NuRenderer { commands: ["ll".to_string()].into() };
+        assert_eq!(
+            renderer.render_definition(&chained),
+            "def --env --wrapped gs [...rest] { cd src ; ll -a ...$rest }"
+        );
+
+        assert_eq!(NuRenderer::default().render_definition(&env("it's")), "$env.KUBECONFIG = \"it's\"");
+        assert_eq!(
+            NuRenderer::default().render_definition(&env("~/k (1):$PATH")),
+            "$env.KUBECONFIG = $\"([$env.HOME?] | flatten | compact | str join (char esep))/k \\(1):\
+             ([$env.PATH?] | flatten | compact | str join (char esep))\""
+        );
+    }
+}
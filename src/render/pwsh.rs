@@ -0,0 +1,194 @@
+use super::{render_words, value_parts, word_parts, ShellRenderer, ValuePart};
+use crate::ast::ShellOp;
+use crate::template::{CommandTemplate, TemplatePart};
+
+/// PowerShell 7. `Set-Alias` cannot take arguments, so only a bare command
+/// name becomes an alias; everything else is a function reading `$args`.
+pub struct PwshRenderer;
+
+impl PwshRenderer {
+    /// `op` in PowerShell syntax; `None` for `&`, subshells and redirections it lacks.
+    fn op(op: &ShellOp) -> Option<&'static str> {
+        match op {
+            ShellOp::Pipe => Some("|"),
+            ShellOp::And => Some("&&"),
+            ShellOp::Or => Some("||"),
+            ShellOp::Seq => Some(";"),
+            ShellOp::Redirect(r) => match r.as_str() {
+                ">" | "1>" => Some(">"),
+                ">>" | "1>>" => Some(">>"),
+                "2>" => Some("2>"),
+                "2>>" => Some("2>>"),
+                "2>&1" => Some("2>&1"),
+                "&>" => Some("*>"),
+                _ => None,
+            },
+            // `&` makes a job, and a script block would not see the function's `$args`.
+            ShellOp::Background | ShellOp::SubshellOpen | ShellOp::SubshellClose => None,
+        }
+    }
+
+    /// A string in expression mode, where even a plain word would be a command.
+    fn string(text: &str) -> String {
+        format!("'{}'", text.replace('\'', "''"))
+    }
+
+    /// Drop the alias a function would otherwise be shadowed by (`gc`, `gp`, ...).
+    fn unalias(name: &str) -> String {
+        format!("Remove-Item -Force -ErrorAction Ignore Alias:{name}")
+    }
+}
+
+impl ShellRenderer for PwshRenderer {
+    fn slot_ref(&self, n: u32) -> String {
+        format!("$args[{}]", n - 1)
+    }
+    fn default_slot_ref(&self, n: u32, default: &str) -> String {
+        format!("($args[{}] ?? {})", n - 1, Self::string(default))
+    }
+    fn rest_ref(&self, after: u32) -> String {
+        if after == 0 {
+            "@args".to_string()
+        } else {
+            format!("@($args | Select-Object -Skip {after})")
+        }
+    }
+    fn quote_literal(&self, tok: &str) -> String {
+        // Bare words keep `-Flag` a parameter for cmdlets; anything PowerShell
+        // would expand, glob or split is single-quoted, where only ' is special.
+        let bare = tok.starts_with(|c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'))
+            && tok.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=+%~".contains(c));
+        if bare {
+            tok.to_string()
+        } else {
+            Self::string(tok)
+        }
+    }
+    fn render_op(&self, op: &ShellOp) -> String {
+        Self::op(op).unwrap_or(op.as_str()).to_string()
+    }
+    fn supports(&self, t: &CommandTemplate) -> bool {
+        word_parts(t).iter().all(|part| match part {
+            TemplatePart::Op(op) => Self::op(op).is_some(),
+            _ => true,
+        })
+    }
+    fn render_alias(&self, name: &str, command: &str) -> String {
+        format!("Set-Alias -Name {} -Value {} -Scope Global -Force", name, command)
+    }
+    fn render_function(&self, name: &str, statements: &[String]) -> String {
+        format!("{}\nfunction global:{} {{ {} }}", Self::unalias(name), name, statements.join("; "))
+    }
+    fn render_prologue(&self, name: &str, t: &CommandTemplate) -> Vec<String> {
+        let required = t.required_args();
+        if required == 0 {
+            return Vec::new();
+        }
+        vec![format!(
+            "if ($args.Count -lt {}) {{ [Console]::Error.WriteLine({}); return }}",
+            required,
+            Self::string(&t.usage(name))
+        )]
+    }
+    fn render_raw(&self, name: &str, body: &str) -> String {
+        format!("{}\nfunction global:{} {{\n{}\n}}", Self::unalias(name), name, body)
+    }
+    fn render_env(&self, name: &str, value: &str) -> String {
+        let parts = value_parts(value);
+        if let [ValuePart::Text(text)] = parts.as_slice() {
+            return format!("$env:{} = {}", name, Self::string(text));
+        }
+        let interpolated: String = parts
+            .iter()
+            .map(|part| match part {
+                ValuePart::Text(text) => {
+                    text.replace('`', "``").replace('"', "`\"").replace('$', "`$")
+                }
+                ValuePart::Var(var) => format!("$($env:{var})"),
+            })
+            .collect();
+        format!("$env:{} = \"{}\"", name, interpolated)
+    }
+    fn dialect(&self) -> &'static str {
+        "pwsh"
+    }
+    fn render_template_body(&self, t: &CommandTemplate) -> String {
+        render_words(&word_parts(t), |part, command_position| match part {
+            TemplatePart::Literal(s) => {
+                let quoted = self.quote_literal(s);
+                // A quoted command name would be a string; the call operator runs it.
+                if command_position && quoted != *s {
+                    format!("& {quoted}")
+                } else {
+                    quoted
+                }
+            }
+            TemplatePart::Slot(n) => match t.slot_default(*n) {
+                Some(default) => self.default_slot_ref(*n, default),
+                None => self.slot_ref(*n),
+            },
+            TemplatePart::AndThen => "&&".to_string(),
+            TemplatePart::Rest => self.rest_ref(t.max_slot()),
+            TemplatePart::Op(op) => self.render_op(op),
+        })
+    }
+    fn render_alias_definition(&self, name: &str, t: &CommandTemplate) -> String {
+        // Arguments typed after the alias land where its trailing slot or rest is.
+        let words: Vec<TemplatePart> = word_parts(t)
+            .into_iter()
+            .filter(|p| !matches!(p, TemplatePart::Slot(_) | TemplatePart::Rest))
+            .collect();
+        let command = CommandTemplate::new(words);
+        match command.parts.as_slice() {
+            [TemplatePart::Literal(program)] if self.quote_literal(program) == *program => {
+                self.render_alias(name, program)
+            }
+            _ => self.render_function(name, &[format!("{} @args", self.render_template_body(&command))]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::Definition;
+    use crate::render::tests::{env, interior_def, zero_def};
+    use crate::template::SlotSpec;
+
+    #[test]
+    fn pwsh_renders_functions_over_args() {
+        assert_eq!(
+            PwshRenderer.render_definition(&zero_def()),
+            "Remove-Item -Force -ErrorAction Ignore Alias:gs\nfunction global:gs { git status @args }"
+        );
+        let bare = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("git".into()), TemplatePart::Slot(1)]),
+            ..zero_def()
+        };
+        assert_eq!(PwshRenderer.render_definition(&bare), "Set-Alias -Name gs -Value git -Scope Global -Force");
+
+        let mut def = interior_def();
+        def.template.parts.insert(0, TemplatePart::Literal("my tool".into()));
+        def.template.parts.push(TemplatePart::Literal("it's".into()));
+        def.template.parts.push(TemplatePart::Slot(2));
+        def.template.slots.insert(2, SlotSpec::parse("shell=sh").unwrap());
+        assert_eq!(
+            PwshRenderer.render_definition(&def),
+            "Remove-Item -Force -ErrorAction Ignore Alias:dex\nfunction global:dex { \
+             if ($args.Count -lt 1) { [Console]::Error.WriteLine('usage: dex <arg1> [shell=sh]'); return }; \
+             & 'my tool' docker exec $args[0] bash 'it''s' ($args[1] ?? 'sh') }"
+        );
+
+        let background = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("make &".into())]),
+            ..zero_def()
+        };
+        assert_eq!(PwshRenderer.render_definition_in(&background, "pwsh"), None);
+
+        assert_eq!(PwshRenderer.render_definition(&env("it's")), "$env:KUBECONFIG = 'it''s'");
+        assert_eq!(
+            PwshRenderer.render_definition(&env("~/`k\\$:$PATH")),
+            "$env:KUBECONFIG = \"$($env:HOME)/``k`$:$($env:PATH)\""
+        );
+    }
+}
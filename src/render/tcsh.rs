@@ -0,0 +1,171 @@
+use super::{value_parts, word_parts, ShellRenderer, ValuePart};
+use crate::ast::ShellOp;
+use crate::template::{CommandTemplate, TemplatePart};
+
+/// tcsh and csh. There are no functions, so every definition is an alias and
+/// its arguments are history references into the line the alias was used on.
+pub struct TcshRenderer;
+
+impl TcshRenderer {
+    /// `op` in csh syntax; `None` for redirections of stderr alone, which csh lacks.
+    fn op(op: &ShellOp) -> Option<&'static str> {
+        match op {
+            ShellOp::Redirect(r) => match r.as_str() {
+                ">" | "1>" => Some(">"),
+                ">>" | "1>>" => Some(">>"),
+                "<" => Some("<"),
+                "&>" => Some(">&"),
+                "&>>" => Some(">>&"),
+                _ => None,
+            },
+            ShellOp::Pipe => Some("|"),
+            ShellOp::And => Some("&&"),
+            ShellOp::Or => Some("||"),
+            ShellOp::Seq => Some(";"),
+            ShellOp::Background => Some("&"),
+            ShellOp::SubshellOpen => Some("("),
+            ShellOp::SubshellClose => Some(")"),
+        }
+    }
+
+    /// `tok` as the alias body will be re-read on expansion; `None` when it
+    /// holds a `!` or newline, or both kinds of quote are needed.
+    fn word(tok: &str) -> Option<String> {
+        if tok.contains(['!', '\n']) {
+            None
+        } else if !tok.is_empty() && tok.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@".contains(c)) {
+            Some(tok.to_string())
+        } else if !tok.contains('\'') {
+            Some(format!("'{tok}'"))
+        } else if !tok.contains(['$', '"', '`']) {
+            Some(format!("\"{tok}\""))
+        } else {
+            None
+        }
+    }
+
+    /// An alias value in single quotes. `!` is escaped so history references
+    /// are taken when the alias is used, not when it is defined.
+    fn quote_value(value: &str) -> String {
+        format!("'{}'", value.replace('\'', r"'\''").replace('!', r"\!"))
+    }
+}
+
+impl ShellRenderer for TcshRenderer {
+    fn slot_ref(&self, n: u32) -> String {
+        format!("!:{n}")
+    }
+    fn rest_ref(&self, after: u32) -> String {
+        if after == 0 {
+            "!*".to_string()
+        } else {
+            format!("!:{}*", after + 1)
+        }
+    }
+    fn quote_literal(&self, tok: &str) -> String {
+        Self::word(tok).unwrap_or_else(|| format!("'{tok}'"))
+    }
+    fn render_op(&self, op: &ShellOp) -> String {
+        Self::op(op).unwrap_or(op.as_str()).to_string()
+    }
+    fn supports(&self, t: &CommandTemplate) -> bool {
+        // A missing argument is an error in csh, so there is nothing a default could fill.
+        t.slots.values().all(|spec| spec.default.is_none())
+            && word_parts(t).iter().all(|part| match part {
+                TemplatePart::Literal(s) => Self::word(s).is_some(),
+                TemplatePart::Op(op) => Self::op(op).is_some(),
+                _ => true,
+            })
+    }
+    fn render_alias(&self, name: &str, command: &str) -> String {
+        format!("alias {} {}", name, Self::quote_value(command))
+    }
+    fn render_function(&self, name: &str, statements: &[String]) -> String {
+        self.render_alias(name, &statements.join("; "))
+    }
+    fn render_prologue(&self, _name: &str, _t: &CommandTemplate) -> Vec<String> {
+        // csh stops with "Bad ! arg selector" when an argument is missing.
+        Vec::new()
+    }
+    fn supports_raw(&self, body: &str) -> bool {
+        // An alias is one line; `foreach`, `while` and `if … then` blocks need
+        // their own lines, so no multi-line body survives being joined.
+        body.trim().lines().count() <= 1
+    }
+    fn render_raw(&self, name: &str, body: &str) -> String {
+        self.render_alias(name, body.trim())
+    }
+    fn render_env(&self, name: &str, value: &str) -> String {
+        // `$VAR` is an error in csh when VAR is unset; printenv gives "" instead.
+        let quoted: String = value_parts(value)
+            .iter()
+            .map(|part| match part {
+                ValuePart::Text(text) => Self::quote_value(text),
+                ValuePart::Var(var) => format!("\"`printenv {var}`\""),
+            })
+            .collect();
+        format!("setenv {} {}", name, quoted)
+    }
+    fn dialect(&self) -> &'static str {
+        "tcsh"
+    }
+    fn render_alias_definition(&self, name: &str, t: &CommandTemplate) -> String {
+        // Without history references csh appends the arguments itself.
+        let words: Vec<TemplatePart> = word_parts(t)
+            .into_iter()
+            .filter(|p| !matches!(p, TemplatePart::Slot(_) | TemplatePart::Rest))
+            .collect();
+        self.render_alias(name, &self.render_template_body(&CommandTemplate::new(words)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{Definition, DefinitionKind, RawBodies};
+    use crate::render::tests::{env, interior_def, zero_def};
+    use crate::template::SlotSpec;
+
+    #[test]
+    fn tcsh_uses_history_references_and_skips_the_rest() {
+        assert_eq!(TcshRenderer.render_definition(&zero_def()), "alias gs 'git status'");
+        assert_eq!(TcshRenderer.render_definition(&interior_def()), "alias dex 'docker exec \\!:1 bash'");
+
+        let quoted = Definition {
+            template: CommandTemplate::new(vec![TemplatePart::Literal("grep -r \"it's\" '$HOME' | less &".into())]),
+            ..zero_def()
+        };
+        assert_eq!(
+            TcshRenderer.render_definition(&quoted),
+            "alias gs 'grep -r \"it'\\''s\" '\\''$HOME'\\'' | less &'"
+        );
+
+        let mut rest = interior_def();
+        rest.template.parts.push(TemplatePart::Rest);
+        assert!(TcshRenderer.render_definition(&rest).ends_with("bash \\!:2*'"));
+
+        let mut defaulted = interior_def();
+        defaulted.template.slots.insert(1, SlotSpec::parse("c=web").unwrap());
+        assert_eq!(TcshRenderer.render_definition_in(&defaulted, "tcsh"), None);
+        assert_eq!(TcshRenderer.render_definition(&defaulted), "# alman: dex cannot be written in tcsh");
+        for text in ["make 2> err", "echo hi!"] {
+            let def = Definition { template: CommandTemplate::new(vec![TemplatePart::Literal(text.into())]), ..zero_def() };
+            assert!(!TcshRenderer.supports(&def.template), "{text}");
+        }
+
+        let raw = |body: &str| Definition {
+            name: "up".into(),
+            kind: DefinitionKind::Raw(RawBodies([("tcsh".to_string(), body.to_string())].into())),
+            template: CommandTemplate::default(),
+        };
+        assert_eq!(TcshRenderer.render_definition(&raw("cd ..; ls\n")), "alias up 'cd ..; ls'");
+        let block = raw("foreach d (a b)\n echo $d\nend");
+        assert_eq!(TcshRenderer.render_definition_in(&block, "tcsh"), None);
+        assert_eq!(TcshRenderer.render_definition(&block), "# alman: up cannot be written in tcsh");
+
+        assert_eq!(
+            TcshRenderer.render_definition(&env("~/k:$PATH")),
+            "setenv KUBECONFIG \"`printenv HOME`\"'/k:'\"`printenv PATH`\""
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cli::cli_data::InitShell;
+use crate::cli::cli_data::{BashHook, InitShell};
 use askama::Template;
 use std::path::PathBuf;
 
@@ -6,6 +6,10 @@ pub struct ShellOpts {
     pub app_path: String,
     pub data_dir: String,
     pub config_dir: String,
+    /// How the bash integration hooks in; other shells ignore it.
+    pub bash_hook: BashHook,
+    /// Whether the hooks call `alman remind`; off, they never start it.
+    pub reminders: bool,
 }
 
 impl ShellOpts {
@@ -40,6 +44,8 @@ impl ShellOpts {
             app_path,
             data_dir,
             config_dir,
+            bash_hook: BashHook::Auto,
+            reminders: crate::database::persistence::load_config().is_none_or(|c| c.reminders),
         }
     }
 }
@@ -52,6 +58,7 @@ macro_rules! make_template {
             pub app_path: &'a str,
             pub data_dir: &'a str,
             pub config_dir: &'a str,
+            pub reminders: bool,
         }
 
         impl<'a> From<&'a ShellOpts> for $name<'a> {
@@ -60,16 +67,62 @@ macro_rules! make_template {
                     app_path: &opts.app_path,
                     data_dir: &opts.data_dir,
                     config_dir: &opts.config_dir,
+                    reminders: opts.reminders,
                 }
             }
         }
     };
 }
 
-make_template!(Bash, "bash.txt");
+/// Bash also takes the hook strategy, so it is written out rather than made
+/// by `make_template!`.
+#[derive(Template)]
+#[template(path = "bash.txt")]
+pub struct Bash<'a> {
+    pub app_path: &'a str,
+    pub data_dir: &'a str,
+    pub config_dir: &'a str,
+    pub bash_hook: &'a str,
+    pub reminders: bool,
+}
+
+impl<'a> From<&'a ShellOpts> for Bash<'a> {
+    fn from(opts: &'a ShellOpts) -> Self {
+        Self {
+            app_path: &opts.app_path,
+            data_dir: &opts.data_dir,
+            config_dir: &opts.config_dir,
+            bash_hook: opts.bash_hook.as_str(),
+            reminders: opts.reminders,
+        }
+    }
+}
+
+/// Plain POSIX shells have no hook to point out reminders from, so this one
+/// is written out without the `reminders` flag.
+#[derive(Template)]
+#[template(path = "posix.txt")]
+pub struct Posix<'a> {
+    pub app_path: &'a str,
+    pub data_dir: &'a str,
+    pub config_dir: &'a str,
+}
+
+impl<'a> From<&'a ShellOpts> for Posix<'a> {
+    fn from(opts: &'a ShellOpts) -> Self {
+        Self {
+            app_path: &opts.app_path,
+            data_dir: &opts.data_dir,
+            config_dir: &opts.config_dir,
+        }
+    }
+}
+
 make_template!(Zsh, "zsh.txt");
 make_template!(Fish, "fish.txt");
-make_template!(Posix, "posix.txt");
+make_template!(Nu, "nu.txt");
+make_template!(Pwsh, "pwsh.txt");
+make_template!(Tcsh, "tcsh.txt");
 
 pub fn render_shell_init(shell: InitShell, opts: &ShellOpts) -> String {
     match shell {
@@ -77,5 +130,8 @@ pub fn render_shell_init(shell: InitShell, opts: &ShellOpts) -> String {
         InitShell::Zsh => Zsh::from(opts).render().expect("zsh template render failed"),
         InitShell::Fish => Fish::from(opts).render().expect("fish template render failed"),
         InitShell::Posix => Posix::from(opts).render().expect("posix template render failed"),
+        InitShell::Nu => Nu::from(opts).render().expect("nu template render failed"),
+        InitShell::Pwsh => Pwsh::from(opts).render().expect("pwsh template render failed"),
+        InitShell::Tcsh => Tcsh::from(opts).render().expect("tcsh template render failed"),
     }
 }
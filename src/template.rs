@@ -1,18 +1,144 @@
+use crate::ast::{ShellOp, Token};
+use crate::tokenize::quote_word;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+
+/// Stands in for a placeholder while the rest of a template is lexed; a
+/// private-use character, so it cannot clash with typed text.
+const PLACEHOLDER_MARK: char = '\u{E000}';
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TemplatePart {
     Literal(String),
     Slot(u32), // 1-based
+    /// `&&` between two commands of a sequence.
+    AndThen,
+    /// Every argument after the highest-numbered slot, however many there are.
+    Rest,
+    /// Any other shell operator: a pipe, `||`, `;`, a redirection or subshell
+    /// parentheses. Rendered unquoted, in the target shell's syntax.
+    Op(ShellOp),
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+impl TemplatePart {
+    /// Literal for a word, the matching operator part for an operator.
+    pub fn from_token(token: &Token) -> Self {
+        match token {
+            Token::Word(w) => TemplatePart::Literal(w.clone()),
+            Token::Op(ShellOp::And) => TemplatePart::AndThen,
+            Token::Op(op) => TemplatePart::Op(op.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct CommandTemplate {
     pub parts: Vec<TemplatePart>,
+    /// Optional names and defaults, keyed by slot number.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub slots: BTreeMap<u32, SlotSpec>,
+}
+
+/// How a typed command fills a template, from [`CommandTemplate::bind`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    /// Word bound to each slot.
+    pub slots: BTreeMap<u32, String>,
+    /// Words taken by `Rest`.
+    pub rest: Vec<String>,
+    /// Tokens after the end of the template.
+    pub trailing: Vec<Token>,
+    /// How many tokens the template's literals and operators accounted for.
+    pub literal_tokens: usize,
+}
+
+impl Binding {
+    /// Arguments to pass to the definition: slots in order, then the rest.
+    pub fn args(&self) -> Vec<String> {
+        self.slots.values().chain(&self.rest).cloned().collect()
+    }
+}
+
+/// What a slot stands for (`container`) and what it falls back to when omitted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlotSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// What kind of value a slot holds, guessed from the command and past values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotType {
+    Path,
+    GitBranch,
+    Hostname,
+    Container,
+    Number,
+    Word,
+}
+
+impl SlotSpec {
+    /// Parse `name`, `name=default` or `=default`; `None` for blank input.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let (name, default) = match input.split_once('=') {
+            Some((name, default)) => (name.trim(), Some(default.to_string())),
+            None => (input, None),
+        };
+        Some(SlotSpec {
+            name: (!name.is_empty()).then(|| name.to_string()),
+            default,
+        })
+    }
 }
 
 impl CommandTemplate {
+    pub fn new(parts: Vec<TemplatePart>) -> Self {
+        Self { parts, slots: BTreeMap::new() }
+    }
+
+    /// Display name of slot `n`: its given name, or `argN`.
+    pub fn slot_name(&self, n: u32) -> String {
+        self.slots
+            .get(&n)
+            .and_then(|s| s.name.clone())
+            .unwrap_or_else(|| format!("arg{n}"))
+    }
+
+    pub fn slot_default(&self, n: u32) -> Option<&str> {
+        self.slots.get(&n)?.default.as_deref()
+    }
+
+    /// How many arguments must be passed: up to the last slot without a default.
+    pub fn required_args(&self) -> u32 {
+        (1..=self.max_slot())
+            .rev()
+            .find(|n| self.slot_default(*n).is_none())
+            .unwrap_or(0)
+    }
+
+    /// `usage: dex <container> [shell=bash] [args...]`
+    pub fn usage(&self, name: &str) -> String {
+        let mut usage = format!("usage: {name}");
+        let required = self.required_args();
+        for n in 1..=self.max_slot() {
+            let slot = self.slot_name(n);
+            match self.slot_default(n) {
+                Some(default) if n > required => usage.push_str(&format!(" [{slot}={default}]")),
+                _ => usage.push_str(&format!(" <{slot}>")),
+            }
+        }
+        if self.parts.contains(&TemplatePart::Rest) {
+            usage.push_str(" [args...]");
+        }
+        usage
+    }
+
     /// Count distinct Slot indices.
     pub fn slot_count(&self) -> u32 {
         let mut seen: HashSet<u32> = HashSet::new();
@@ -29,13 +155,309 @@ impl CommandTemplate {
     }
 
     /// True iff exactly one slot, it is last, and there are no other slots.
+    /// A named or defaulted slot needs a function for its usage check.
     pub fn only_trailing_single_slot(&self) -> bool {
-        if self.slot_count() != 1 {
+        if self.slot_count() != 1 || !self.slots.is_empty() {
             return false;
         }
         matches!(self.parts.last(), Some(TemplatePart::Slot(1)))
     }
 
+    /// Highest slot index used, or 0 when there are no slots.
+    pub fn max_slot(&self) -> u32 {
+        self.parts
+            .iter()
+            .filter_map(|p| match p {
+                TemplatePart::Slot(n) => Some(*n),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Guess what slot `n` holds from the literals around it and the values it took.
+    pub fn infer_slot_type(&self, n: u32, values: &[String]) -> SlotType {
+        // Literals of the command the slot belongs to (sequences have several),
+        // and whether another slot of that command comes first.
+        let mut command: Vec<&str> = Vec::new();
+        let mut first_slot = true;
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(s) => command.push(s),
+                TemplatePart::AndThen => {
+                    command.clear();
+                    first_slot = true;
+                }
+                TemplatePart::Slot(m) if *m == n => break,
+                TemplatePart::Slot(_) | TemplatePart::Rest => first_slot = false,
+                TemplatePart::Op(_) => {}
+            }
+        }
+        let program = command.first().copied().unwrap_or("");
+        let has = |words: &[&str]| command.iter().any(|w| words.contains(w));
+
+        if !values.is_empty() && values.iter().all(|v| v.parse::<f64>().is_ok()) {
+            SlotType::Number
+        } else if first_slot
+            && matches!(program, "docker" | "podman")
+            && has(&["exec", "logs", "stop", "start", "restart", "rm", "attach", "inspect", "kill"])
+        {
+            SlotType::Container
+        } else if first_slot && program == "git" && has(&["checkout", "switch", "merge", "rebase", "branch"]) {
+            SlotType::GitBranch
+        } else if first_slot && matches!(program, "ssh" | "mosh" | "ping" | "sftp" | "telnet") {
+            SlotType::Hostname
+        } else if !values.is_empty()
+            && values.iter().all(|v| v.contains('/') || v.starts_with('.') || v.starts_with('~'))
+        {
+            SlotType::Path
+        } else {
+            SlotType::Word
+        }
+    }
+
+    /// The program the template runs first, when that is a literal word.
+    pub fn program(&self) -> Option<String> {
+        match self.parts.first()? {
+            TemplatePart::Literal(text) => crate::defaults::default_tokenizer()
+                .lex(text)
+                .first()
+                .and_then(Token::as_word)
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// True when the template chains several commands with `&&`.
+    pub fn is_sequence(&self) -> bool {
+        self.parts.contains(&TemplatePart::AndThen)
+    }
+
+    /// Match a typed command against the template. Literals and operators must
+    /// match exactly; a slot takes any one word (the same word wherever the slot
+    /// repeats) and `Rest` takes the words up to the next operator. Tokens after
+    /// the last part are left over as trailing arguments.
+    pub fn bind(&self, tokens: &[Token]) -> Option<Binding> {
+        let mut slots: BTreeMap<u32, String> = BTreeMap::new();
+        let mut rest = Vec::new();
+        let mut pos = 0;
+        let mut literal_tokens = 0;
+        for part in &self.parts {
+            match part {
+                // Plain aliases keep the whole command in one literal, so a literal
+                // that isn't a single typed word is matched as the words it lexes to.
+                TemplatePart::Literal(lit) if tokens.get(pos)?.as_word() == Some(lit) => {
+                    pos += 1;
+                    literal_tokens += 1;
+                }
+                TemplatePart::Literal(lit) => {
+                    let expected = crate::defaults::default_tokenizer().lex(lit);
+                    if expected.len() < 2 || !tokens[pos..].starts_with(&expected) {
+                        return None;
+                    }
+                    pos += expected.len();
+                    literal_tokens += expected.len();
+                }
+                TemplatePart::Slot(n) => {
+                    let word = tokens.get(pos)?.as_word()?;
+                    if slots.entry(*n).or_insert_with(|| word.to_string()) != word {
+                        return None;
+                    }
+                    pos += 1;
+                }
+                TemplatePart::AndThen => {
+                    (tokens.get(pos)? == &Token::Op(ShellOp::And)).then_some(())?;
+                    pos += 1;
+                    literal_tokens += 1;
+                }
+                TemplatePart::Op(op) => {
+                    (tokens.get(pos)? == &Token::Op(op.clone())).then_some(())?;
+                    pos += 1;
+                    literal_tokens += 1;
+                }
+                TemplatePart::Rest => {
+                    while let Some(word) = tokens.get(pos).and_then(Token::as_word) {
+                        rest.push(word.to_string());
+                        pos += 1;
+                    }
+                }
+            }
+        }
+        Some(Binding { slots, rest, trailing: tokens[pos..].to_vec(), literal_tokens })
+    }
+
+    /// Parse the template mini-language used by `alman add -c`: a command line
+    /// where `{1}`, `{2}`… or `{name}` stand for arguments, `{name=default}` or
+    /// `{1:name=default}` names a slot and gives it a default, and `{...}` takes
+    /// any remaining arguments, so it must end its command. Braces inside
+    /// quotes, after `$` or `@`, empty (`{}`), opening a brace group (`{ ls; }`)
+    /// or holding a brace expansion (`{a,b}`, `{1..3}`) are literal.
+    /// Text without placeholders is a plain alias: one literal, the whole command.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("the command is empty".to_string());
+        }
+        let (marked, placeholders) = mark_placeholders(text);
+        if placeholders.is_empty() {
+            return Ok(Self::new(vec![TemplatePart::Literal(text.to_string())]));
+        }
+        let unmark = |word: &str| {
+            let mut out = word.to_string();
+            for (i, inner) in placeholders.iter().enumerate() {
+                out = out.replace(&format!("{PLACEHOLDER_MARK}{i}{PLACEHOLDER_MARK}"), &format!("{{{inner}}}"));
+            }
+            out
+        };
+
+        // Resolve each placeholder to a part; named ones are numbered in order
+        // of first appearance.
+        let mut resolved: Vec<TemplatePart> = Vec::with_capacity(placeholders.len());
+        let mut slots: BTreeMap<u32, SlotSpec> = BTreeMap::new();
+        let mut names: Vec<String> = Vec::new();
+        let (mut numbered, mut named) = (false, false);
+        for inner in &placeholders {
+            if inner == "..." {
+                if resolved.contains(&TemplatePart::Rest) {
+                    return Err("only one `{...}` is allowed".to_string());
+                }
+                resolved.push(TemplatePart::Rest);
+                continue;
+            }
+            let (head, default) = match inner.split_once('=') {
+                Some((head, default)) => (head, Some(default.to_string())),
+                None => (inner.as_str(), None),
+            };
+            let (number, name) = match head.split_once(':') {
+                Some((n, name)) => (Some(n), Some(name)),
+                None if head.starts_with(|c: char| c.is_ascii_digit()) => (Some(head), None),
+                None => (None, Some(head)),
+            };
+            let unknown = || format!("unknown placeholder `{{{inner}}}`: use {{1}}, {{name}}, {{name=default}} or {{...}}");
+            if name.is_some_and(|n| !is_slot_name(n)) {
+                return Err(unknown());
+            }
+            let n = match number {
+                Some(digits) => {
+                    numbered = true;
+                    match digits.parse::<u32>() {
+                        Ok(0) => return Err(format!("`{{{inner}}}`: slots are numbered from 1")),
+                        Ok(n) if digits.chars().all(|c| c.is_ascii_digit()) => n,
+                        _ => return Err(unknown()),
+                    }
+                }
+                None => {
+                    named = true;
+                    let name = name.unwrap_or_default();
+                    match names.iter().position(|known| known == name) {
+                        Some(i) => i as u32 + 1,
+                        None => {
+                            names.push(name.to_string());
+                            names.len() as u32
+                        }
+                    }
+                }
+            };
+            if numbered && named {
+                return Err("numbered and named placeholders are mixed: number every slot (`{1:name}`) or none".to_string());
+            }
+            let spec = SlotSpec { name: name.map(str::to_string), default };
+            if spec != SlotSpec::default() {
+                match slots.get(&n) {
+                    Some(known) if *known != spec => {
+                        return Err(format!("slot {n} is given as both `{}` and `{{{inner}}}`", spec_text(n, known)));
+                    }
+                    _ => {
+                        slots.insert(n, spec);
+                    }
+                }
+            }
+            resolved.push(TemplatePart::Slot(n));
+        }
+        let max = resolved
+            .iter()
+            .filter_map(|p| match p {
+                TemplatePart::Slot(n) => Some(*n),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        if let Some(missing) = (1..=max).find(|n| !resolved.contains(&TemplatePart::Slot(*n))) {
+            return Err(format!("slot {{{missing}}} is never used: number slots from 1 without gaps"));
+        }
+
+        let mut parts = Vec::new();
+        for token in crate::defaults::default_tokenizer().lex(&marked) {
+            match token {
+                Token::Word(word) if word.contains(PLACEHOLDER_MARK) => {
+                    let index = word
+                        .strip_prefix(PLACEHOLDER_MARK)
+                        .and_then(|w| w.strip_suffix(PLACEHOLDER_MARK))
+                        .and_then(|w| w.parse::<usize>().ok());
+                    match index {
+                        Some(i) => parts.push(resolved[i].clone()),
+                        None => {
+                            return Err(format!(
+                                "a placeholder must be a word of its own, not part of `{}`",
+                                unmark(&word)
+                            ))
+                        }
+                    }
+                }
+                token => parts.push(TemplatePart::from_token(&token)),
+            }
+        }
+        let rest_followed = parts.windows(2).any(|pair| {
+            pair[0] == TemplatePart::Rest && matches!(pair[1], TemplatePart::Literal(_) | TemplatePart::Slot(_))
+        });
+        if rest_followed {
+            return Err("`{...}` takes every remaining argument, so it must be the last word of its command".to_string());
+        }
+        Ok(Self { parts, slots })
+    }
+
+    /// The template in the mini-language read by [`CommandTemplate::parse`].
+    /// Slots are written by name when every slot has one, otherwise by number.
+    pub fn to_text(&self) -> String {
+        if let [TemplatePart::Literal(command)] = self.parts.as_slice() {
+            if self.slots.is_empty() {
+                return command.clone();
+            }
+        }
+        let mut order: Vec<u32> = Vec::new();
+        for part in &self.parts {
+            if let TemplatePart::Slot(n) = part {
+                if !order.contains(n) {
+                    order.push(*n);
+                }
+            }
+        }
+        let by_name = order.iter().enumerate().all(|(i, n)| {
+            *n == i as u32 + 1 && self.slots.get(n).is_some_and(|s| s.name.is_some())
+        });
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Literal(s) => quote_word(s),
+                TemplatePart::Slot(n) => match self.slots.get(n) {
+                    Some(spec) if by_name => {
+                        let name = spec.name.as_deref().unwrap_or_default();
+                        match &spec.default {
+                            Some(default) => format!("{{{name}={default}}}"),
+                            None => format!("{{{name}}}"),
+                        }
+                    }
+                    Some(spec) => spec_text(*n, spec),
+                    None => format!("{{{n}}}"),
+                },
+                TemplatePart::AndThen => "&&".to_string(),
+                TemplatePart::Rest => "{...}".to_string(),
+                TemplatePart::Op(op) => op.as_str().to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
@@ -45,59 +467,261 @@ impl CommandTemplate {
     }
 }
 
+/// A valid slot name: a letter or `_`, then letters, digits, `_` or `-`.
+fn is_slot_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// `{1}`, `{1:name}`, `{1=default}` or `{1:name=default}`.
+fn spec_text(n: u32, spec: &SlotSpec) -> String {
+    let mut text = n.to_string();
+    if let Some(name) = &spec.name {
+        text = format!("{text}:{name}");
+    }
+    if let Some(default) = &spec.default {
+        text = format!("{text}={default}");
+    }
+    format!("{{{text}}}")
+}
+
+/// Replace every placeholder in `text` with a numbered [`PLACEHOLDER_MARK`]
+/// pair and return the text with the placeholders' contents. Quoting and
+/// escapes are followed only far enough to leave quoted braces alone.
+fn mark_placeholders(text: &str) -> (String, Vec<String>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut placeholders = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                out.push(c);
+                if let Some(&next) = chars.get(i + 1) {
+                    out.push(next);
+                }
+                i += 2;
+                continue;
+            }
+            (Some('"'), '"') => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '{') if !matches!(i.checked_sub(1).map(|p| chars[p]), Some('$' | '@')) => {
+                let close = chars[i + 1..].iter().position(|&c| c == '}' || c == '\n');
+                if let Some(len) = close.filter(|&len| chars[i + 1 + len] == '}') {
+                    let inner: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let head = inner.split('=').next().unwrap_or_default();
+                    let expansion = head.contains(',') || (head.contains("..") && inner != "...");
+                    // `{ cmd; }` is a brace group; any other word in braces is a placeholder.
+                    if !inner.is_empty() && !inner.starts_with(char::is_whitespace) && !expansion {
+                        out.push(PLACEHOLDER_MARK);
+                        out.push_str(&placeholders.len().to_string());
+                        out.push(PLACEHOLDER_MARK);
+                        placeholders.push(inner);
+                        i += len + 2;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+    (out, placeholders)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn slot_count_distinct() {
-        let t = CommandTemplate {
-            parts: vec![
-                TemplatePart::Literal("git".into()),
-                TemplatePart::Slot(1),
-                TemplatePart::Slot(2),
-            ],
-        };
+        let t = CommandTemplate::new(vec![
+            TemplatePart::Literal("git".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Slot(2),
+        ]);
         assert_eq!(t.slot_count(), 2);
         assert!(!t.is_zero_slot());
     }
 
     #[test]
     fn trailing_single_slot_detection() {
-        let trailing = CommandTemplate {
-            parts: vec![TemplatePart::Literal("git".into()), TemplatePart::Slot(1)],
-        };
+        let trailing = CommandTemplate::new(vec![TemplatePart::Literal("git".into()), TemplatePart::Slot(1)]);
         assert!(trailing.only_trailing_single_slot());
 
-        let interior = CommandTemplate {
-            parts: vec![
-                TemplatePart::Literal("docker".into()),
-                TemplatePart::Literal("exec".into()),
-                TemplatePart::Slot(1),
-                TemplatePart::Literal("bash".into()),
-            ],
-        };
+        let interior = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("bash".into()),
+        ]);
         assert!(!interior.only_trailing_single_slot());
 
-        let zero = CommandTemplate {
-            parts: vec![TemplatePart::Literal("git status".into())],
-        };
+        let zero = CommandTemplate::new(vec![TemplatePart::Literal("git status".into())]);
         assert!(!zero.only_trailing_single_slot());
         assert!(zero.is_zero_slot());
     }
 
+    #[test]
+    fn usage_names_slots_and_defaults() {
+        let mut t = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Slot(2),
+        ]);
+        t.slots.insert(1, SlotSpec::parse("container").unwrap());
+        t.slots.insert(2, SlotSpec::parse("shell=bash").unwrap());
+        assert_eq!(t.required_args(), 1);
+        assert_eq!(t.usage("dex"), "usage: dex <container> [shell=bash]");
+
+        // A default before a required slot cannot be used.
+        t.slots.remove(&2);
+        t.slots.insert(1, SlotSpec::parse("=web").unwrap());
+        assert_eq!(t.required_args(), 2);
+        assert_eq!(t.usage("dex"), "usage: dex <arg1> <arg2>");
+        assert_eq!(SlotSpec::parse("  "), None);
+    }
+
+    #[test]
+    fn infers_slot_types_from_command_and_values() {
+        let words = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let dex = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+        ]);
+        assert_eq!(dex.infer_slot_type(1, &words(&["web", "db"])), SlotType::Container);
+
+        let vim = CommandTemplate::new(vec![TemplatePart::Literal("vim".into()), TemplatePart::Slot(1)]);
+        assert_eq!(vim.infer_slot_type(1, &words(&["src/main.rs", "./x"])), SlotType::Path);
+        assert_eq!(vim.infer_slot_type(1, &words(&["8080", "3"])), SlotType::Number);
+        assert_eq!(vim.infer_slot_type(1, &words(&["notes", "todo"])), SlotType::Word);
+
+        // In a sequence only the slot's own command counts.
+        let seq = CommandTemplate::new(vec![
+            TemplatePart::Literal("git".into()),
+            TemplatePart::Literal("checkout".into()),
+            TemplatePart::Literal("main".into()),
+            TemplatePart::AndThen,
+            TemplatePart::Literal("ssh".into()),
+            TemplatePart::Slot(1),
+        ]);
+        assert_eq!(seq.infer_slot_type(1, &words(&["box"])), SlotType::Hostname);
+    }
+
+    #[test]
+    fn binds_typed_commands() {
+        use crate::tokenize::Tokenizer;
+        let tokens = |s: &str| crate::tokenize::ShellTokenizer.lex(s);
+        let dex = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Literal("-it".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("bash".into()),
+        ]);
+        let b = dex.bind(&tokens("docker exec -it web bash -l | tee log")).unwrap();
+        assert_eq!(b.args(), vec!["web"]);
+        assert_eq!(crate::tokenize::tokens_to_text(&b.trailing), "-l | tee log");
+        assert!(dex.bind(&tokens("docker exec -it web sh")).is_none());
+        assert!(dex.bind(&tokens("docker exec -it")).is_none());
+
+        let rg = CommandTemplate::new(vec![TemplatePart::Literal("rg".into()), TemplatePart::Rest]);
+        let b = rg.bind(&tokens("rg foo src && ls")).unwrap();
+        assert_eq!(b.args(), vec!["foo", "src"]);
+        assert_eq!(b.trailing.len(), 2);
+
+        let plain = CommandTemplate::new(vec![TemplatePart::Literal("git commit -m 'fix bug'".into())]);
+        let b = plain.bind(&tokens("git commit -m 'fix bug' --amend")).unwrap();
+        assert_eq!(b.literal_tokens, 4);
+        assert_eq!(b.trailing.len(), 1);
+
+        let twice = CommandTemplate::new(vec![TemplatePart::Slot(1), TemplatePart::Slot(1)]);
+        assert!(twice.bind(&tokens("a b")).is_none());
+    }
+
     #[test]
     fn json_round_trip() {
-        let t = CommandTemplate {
-            parts: vec![
+        let t = CommandTemplate::new(vec![
+            TemplatePart::Literal("docker".into()),
+            TemplatePart::Literal("exec".into()),
+            TemplatePart::Slot(1),
+            TemplatePart::Literal("bash".into()),
+        ]);
+        let json = t.to_json();
+        let back = CommandTemplate::from_json(&json).expect("round trip");
+        assert_eq!(t, back);
+    }
+
+    #[test]
+    fn parses_the_template_mini_language() {
+        let dex = CommandTemplate::parse("docker exec -it {1} bash").unwrap();
+        assert_eq!(
+            dex.parts,
+            vec![
                 TemplatePart::Literal("docker".into()),
                 TemplatePart::Literal("exec".into()),
+                TemplatePart::Literal("-it".into()),
                 TemplatePart::Slot(1),
                 TemplatePart::Literal("bash".into()),
-            ],
-        };
-        let json = t.to_json();
-        let back = CommandTemplate::from_json(&json).expect("round trip");
-        assert_eq!(t, back);
+            ]
+        );
+        assert!(dex.slots.is_empty());
+
+        let named = CommandTemplate::parse("docker exec -it {container} {shell=bash -l} && echo {container} {...}").unwrap();
+        assert!(CommandTemplate::parse("grep {1} {...} | sort").is_ok());
+        assert_eq!(named.max_slot(), 2);
+        assert_eq!(named.slot_name(1), "container");
+        assert_eq!(named.slot_default(2), Some("bash -l"));
+        assert!(named.parts.contains(&TemplatePart::AndThen));
+        assert_eq!(named.parts.last(), Some(&TemplatePart::Rest));
+        assert_eq!(CommandTemplate::parse(&named.to_text()).unwrap(), named);
+
+        let numbered = CommandTemplate::parse("scp {2} {1:host}:{...}");
+        assert!(numbered.unwrap_err().contains("word of its own"));
+        let mut partly_named = CommandTemplate::parse("cp {2:to} {1}").unwrap();
+        assert_eq!(partly_named.to_text(), "cp {2:to} {1}");
+        partly_named.slots.clear();
+        assert_eq!(CommandTemplate::parse(&partly_named.to_text()).unwrap(), partly_named);
+
+        // Quoted, `${…}`, `@{…}`, empty and brace-expansion braces stay literal.
+        for plain in [
+            "find . -name '*.rs' -exec wc -l {} +",
+            "echo \"{1}\" '{name}' ${HOME} @{u} \\{x}",
+            "mkdir -p src/{bin,lib} && touch f{1..3}",
+            "{ ls; } > out",
+        ] {
+            let t = CommandTemplate::parse(plain).unwrap_or_else(|e| panic!("{plain}: {e}"));
+            assert!(t.is_zero_slot() && !t.parts.contains(&TemplatePart::Rest), "{plain}");
+        }
+        assert_eq!(
+            CommandTemplate::parse(" git status ").unwrap().parts,
+            vec![TemplatePart::Literal("git status".into())]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        let err = |text: &str| CommandTemplate::parse(text).unwrap_err();
+        assert!(err("cp {1} {3}").contains("slot {2} is never used"));
+        assert!(err("echo {0}").contains("numbered from 1"));
+        assert!(err("echo {1x}").contains("unknown placeholder `{1x}`"));
+        assert!(err("echo {na!me}").contains("unknown placeholder"));
+        assert!(err("cp {1} {dest}").contains("mixed"));
+        assert!(err("echo {...} {...}").contains("only one"));
+        assert!(err("x {...} {1}").contains("last word"));
+        assert!(err("x {...} y").contains("last word"));
+        assert!(err("echo {foo bar}").contains("unknown placeholder `{foo bar}`"));
+        assert!(err("echo {a=1} {a=2}").contains("slot 1 is given as both"));
+        assert!(err("ssh {host}:22").contains("word of its own"));
+        assert!(err("   ").contains("empty"));
     }
 }
@@ -1,11 +1,315 @@
+use crate::ast::{CommandList, ShellOp, Token};
+
 pub trait Tokenizer: Send + Sync {
-    fn tokenize(&self, cmd: &str) -> Vec<String>;
-}
+    /// Words (quotes removed) and shell operators, in order.
+    fn lex(&self, cmd: &str) -> Vec<Token>;
 
-pub struct ShlexTokenizer;
+    /// The command line as lists, pipelines and simple commands; `None` when its
+    /// operators don't form a valid command line.
+    fn parse(&self, cmd: &str) -> Option<CommandList> {
+        CommandList::parse(&self.lex(cmd))
+    }
 
-impl Tokenizer for ShlexTokenizer {
+    /// Every token as text, operators spelled as in POSIX shells.
     fn tokenize(&self, cmd: &str) -> Vec<String> {
-        shlex::split(cmd).unwrap_or_else(|| cmd.split_whitespace().map(str::to_owned).collect())
+        self.lex(cmd).into_iter().map(Token::into_text).collect()
+    }
+}
+
+/// POSIX-style lexer: handles quoting and backslash escapes and splits out
+/// `|`, `||`, `&&`, `&`, `;`, `(`, `)` and redirections (`>`, `>>`, `<`, `2>`,
+/// `2>&1`, `&>`). `$(…)` stays part of its word, and an unterminated quote runs
+/// to the end of the line.
+pub struct ShellTokenizer;
+
+impl Tokenizer for ShellTokenizer {
+    fn lex(&self, cmd: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        // A word is pending even when empty (`''`); `quoted` blocks fd prefixes (`'2'>`).
+        let mut in_word = false;
+        let mut quoted = false;
+        let mut chars = cmd.chars().peekable();
+
+        macro_rules! flush {
+            () => {
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                    quoted = false;
+                }
+            };
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => flush!(),
+                '\'' => {
+                    in_word = true;
+                    quoted = true;
+                    for c in chars.by_ref() {
+                        if c == '\'' {
+                            break;
+                        }
+                        word.push(c);
+                    }
+                }
+                '"' => {
+                    in_word = true;
+                    quoted = true;
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' if matches!(chars.peek(), Some('"' | '\\' | '$' | '`')) => {
+                                word.push(chars.next().unwrap_or_default());
+                            }
+                            _ => word.push(c),
+                        }
+                    }
+                }
+                '\\' => {
+                    in_word = true;
+                    quoted = true;
+                    if let Some(c) = chars.next() {
+                        word.push(c);
+                    }
+                }
+                '#' if !in_word => break,
+                '$' if chars.peek() == Some(&'(') => {
+                    // Command substitution: keep it verbatim, parentheses balanced.
+                    in_word = true;
+                    word.push('$');
+                    let mut depth = 0;
+                    for c in chars.by_ref() {
+                        word.push(c);
+                        match c {
+                            '(' => depth += 1,
+                            ')' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                '|' => {
+                    flush!();
+                    if chars.next_if_eq(&'|').is_some() {
+                        tokens.push(Token::Op(ShellOp::Or));
+                    } else if chars.next_if_eq(&'&').is_some() {
+                        // `|&` pipes stderr too.
+                        tokens.push(Token::Op(ShellOp::Redirect("2>&1".into())));
+                        tokens.push(Token::Op(ShellOp::Pipe));
+                    } else {
+                        tokens.push(Token::Op(ShellOp::Pipe));
+                    }
+                }
+                '&' => {
+                    flush!();
+                    if chars.next_if_eq(&'&').is_some() {
+                        tokens.push(Token::Op(ShellOp::And));
+                    } else if chars.next_if_eq(&'>').is_some() {
+                        let op = if chars.next_if_eq(&'>').is_some() { "&>>" } else { "&>" };
+                        tokens.push(Token::Op(ShellOp::Redirect(op.into())));
+                    } else {
+                        tokens.push(Token::Op(ShellOp::Background));
+                    }
+                }
+                ';' => {
+                    flush!();
+                    tokens.push(Token::Op(ShellOp::Seq));
+                }
+                '(' if !in_word => tokens.push(Token::Op(ShellOp::SubshellOpen)),
+                ')' => {
+                    flush!();
+                    tokens.push(Token::Op(ShellOp::SubshellClose));
+                }
+                '<' | '>' => {
+                    // A bare number right before the operator is its file descriptor.
+                    let mut op = String::new();
+                    if in_word && !quoted && !word.is_empty() && word.chars().all(|d| d.is_ascii_digit()) {
+                        op = std::mem::take(&mut word);
+                        in_word = false;
+                    }
+                    flush!();
+                    op.push(c);
+                    if c == '>' {
+                        if let Some(n) = chars.next_if(|n| *n == '>' || *n == '|') {
+                            op.push(n);
+                        }
+                    }
+                    if c == '<' {
+                        while let Some(n) = chars.next_if_eq(&'<') {
+                            op.push(n);
+                        }
+                    }
+                    if let Some(amp) = chars.next_if_eq(&'&') {
+                        op.push(amp);
+                        while let Some(d) = chars.next_if(|d| d.is_ascii_digit() || *d == '-') {
+                            op.push(d);
+                        }
+                    }
+                    tokens.push(Token::Op(ShellOp::Redirect(op)));
+                }
+                _ => {
+                    in_word = true;
+                    word.push(c);
+                }
+            }
+        }
+        if in_word {
+            tokens.push(Token::Word(word));
+        }
+        tokens
+    }
+}
+
+/// One word of a command line, with an option and its value kept together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arg<'a> {
+    Positional(&'a str),
+    Flag { flag: &'a str, value: Option<&'a str> },
+    Op(&'a ShellOp),
+}
+
+impl Arg<'_> {
+    /// The tokens this argument was made from, operators spelled out.
+    pub fn tokens(&self) -> Vec<&str> {
+        match *self {
+            Arg::Positional(word) => vec![word],
+            Arg::Flag { flag, value } => std::iter::once(flag).chain(value).collect(),
+            Arg::Op(op) => vec![op.as_str()],
+        }
+    }
+}
+
+/// Pair every flag with the non-flag token right after it, if any.
+///
+/// Without knowing each program's options this is a guess: `ls -l src` pairs
+/// `src` with `-l`. That is harmless for templating since the original token
+/// order is kept. `--name=value` stays a single flag and everything after a
+/// bare `--` is positional. Operators pass through and start a fresh command.
+pub fn split_flags(tokens: &[Token]) -> Vec<Arg<'_>> {
+    let mut args = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    let mut options_ended = false;
+    while i < tokens.len() {
+        let token = match &tokens[i] {
+            Token::Word(w) => w.as_str(),
+            Token::Op(op) => {
+                args.push(Arg::Op(op));
+                options_ended = false;
+                i += 1;
+                continue;
+            }
+        };
+        if options_ended || !is_flag(token) {
+            options_ended |= token == "--";
+            args.push(Arg::Positional(token));
+            i += 1;
+            continue;
+        }
+        let value = tokens
+            .get(i + 1)
+            .and_then(Token::as_word)
+            .filter(|next| !is_flag(next) && *next != "--" && !token.contains('='));
+        args.push(Arg::Flag { flag: token, value });
+        i += 1 + value.is_some() as usize;
+    }
+    args
+}
+
+pub fn is_flag(token: &str) -> bool {
+    token.starts_with('-') && token != "-" && token != "--"
+}
+
+/// True when `token` reads back as itself without quoting.
+pub fn is_plain_word(token: &str) -> bool {
+    !token.is_empty()
+        && !token.chars().any(|c| c.is_whitespace() || "'\"\\$`;&|<>()*?[]{}~#!".contains(c))
+}
+
+/// `word` as a POSIX shell word: unchanged when plain, single-quoted otherwise.
+pub fn quote_word(word: &str) -> String {
+    if is_plain_word(word) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Command-line text for `tokens`, quoting words that need it.
+pub fn tokens_to_text(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|t| match t {
+            Token::Word(w) => quote_word(w),
+            Token::Op(op) => op.as_str().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(s: &str) -> Token {
+        Token::Word(s.into())
+    }
+
+    #[test]
+    fn lexes_operators_outside_quotes() {
+        let op = |s: &str| Token::Op(ShellOp::Redirect(s.into()));
+        assert_eq!(
+            ShellTokenizer.lex(r#"ps aux|grep "a|b" 2>&1 >>log && (cd x; ls '>') || echo \; # done"#),
+            vec![
+                word("ps"),
+                word("aux"),
+                Token::Op(ShellOp::Pipe),
+                word("grep"),
+                word("a|b"),
+                op("2>&1"),
+                op(">>"),
+                word("log"),
+                Token::Op(ShellOp::And),
+                Token::Op(ShellOp::SubshellOpen),
+                word("cd"),
+                word("x"),
+                Token::Op(ShellOp::Seq),
+                word("ls"),
+                word(">"),
+                Token::Op(ShellOp::SubshellClose),
+                Token::Op(ShellOp::Or),
+                word("echo"),
+                word(";"),
+            ]
+        );
+        assert_eq!(ShellTokenizer.lex("echo $(date +%s) &"), vec![
+            word("echo"),
+            word("$(date +%s)"),
+            Token::Op(ShellOp::Background),
+        ]);
+        assert_eq!(ShellTokenizer.lex("git commit -m ''"), vec![word("git"), word("commit"), word("-m"), word("")]);
+    }
+
+    #[test]
+    fn pairs_flags_with_their_values() {
+        let tokens = ShellTokenizer.lex("kubectl logs -n prod -f --since=1h pod-a -- -x");
+        assert_eq!(
+            split_flags(&tokens),
+            vec![
+                Arg::Positional("kubectl"),
+                Arg::Positional("logs"),
+                Arg::Flag { flag: "-n", value: Some("prod") },
+                Arg::Flag { flag: "-f", value: None },
+                Arg::Flag { flag: "--since=1h", value: None },
+                Arg::Positional("pod-a"),
+                Arg::Positional("--"),
+                Arg::Positional("-x"),
+            ]
+        );
     }
 }
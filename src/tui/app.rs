@@ -1,7 +1,7 @@
 use crate::database::database_structs::Command;
 use crate::database::db::now_secs;
 use crate::ops::alias_suggestions::{AliasSuggester, AliasSuggestion};
-use crate::ops::get_suggestions::{query_filtered_commands, query_top_commands};
+use crate::ops::get_suggestions::{current_scope, query_filtered_commands, query_top_commands};
 use ratatui::widgets::ListState;
 use rusqlite::Connection;
 
@@ -15,6 +15,7 @@ pub enum AppMode {
     RemoveAliasConfirmation,
     ChangeAliasStep1,
     ChangeAliasStep2,
+    ChangeAliasStep3,
     ListAliases,
     Templates,
     TemplatesNameInput,
@@ -50,6 +51,12 @@ pub struct App {
     pub change_old_command: Option<String>,
     pub change_new_alias: String,
     pub change_new_alias_cursor_position: usize,
+    /// The definition's body as editable text, before and while it is edited.
+    pub change_old_text: String,
+    pub change_new_command: String,
+    pub change_new_command_cursor_position: usize,
+    /// False for raw functions, whose bodies are not edited here.
+    pub change_command_editable: bool,
     pub change_alias_suggestions: Vec<AliasSuggestion>,
     pub change_alias_suggestions_state: ListState,
     pub aliases: Vec<(String, String)>,
@@ -66,6 +73,12 @@ pub struct App {
     /// Name being typed for a selected template.
     pub template_name_input: String,
     pub template_name_cursor_position: usize,
+    /// Named template whose slots are being named, and the slot being asked
+    /// about (1-based) once the definition name has been entered.
+    pub template_draft: Option<(String, crate::template::CommandTemplate)>,
+    pub template_slot: u32,
+    /// Directory the command list is ranked for; `None` ranks globally.
+    pub scope: Option<std::path::PathBuf>,
 }
 
 impl std::fmt::Debug for App {
@@ -117,6 +130,10 @@ impl App {
             change_old_command: None,
             change_new_alias: String::new(),
             change_new_alias_cursor_position: 0,
+            change_old_text: String::new(),
+            change_new_command: String::new(),
+            change_new_command_cursor_position: 0,
+            change_command_editable: true,
             change_alias_suggestions: Vec::new(),
             change_alias_suggestions_state,
             aliases: Vec::new(),
@@ -134,35 +151,16 @@ impl App {
             },
             template_name_input: String::new(),
             template_name_cursor_position: 0,
+            template_draft: None,
+            template_slot: 0,
+            scope: None,
         }
     }
 
     /// Mine command templates from recent events into `self.mined_templates`.
     pub fn load_templates(&mut self, conn: &Connection) {
-        let tokenizer = crate::defaults::default_tokenizer();
-        let events: Vec<String> = {
-            let mut stmt = match conn
-                .prepare("SELECT command FROM events ORDER BY ts DESC LIMIT 2000")
-            {
-                Ok(s) => s,
-                Err(_) => {
-                    self.mined_templates = Vec::new();
-                    return;
-                }
-            };
-            let collected = match stmt.query_map([], |r| r.get(0)) {
-                Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
-                Err(_) => Vec::new(),
-            };
-            collected
-        };
-        let tokenized: Vec<Vec<String>> = events
-            .iter()
-            .map(|e| tokenizer.tokenize(e))
-            .filter(|t| !t.is_empty())
-            .collect();
-        let miner = crate::defaults::default_miner();
-        self.mined_templates = miner.mine(&tokenized);
+        self.mined_templates =
+            crate::ops::get_templates::mine_recent_templates(conn).unwrap_or_default();
         self.templates_state.select(if self.mined_templates.is_empty() {
             None
         } else {
@@ -177,11 +175,21 @@ impl App {
 
     pub fn load_commands(&mut self, conn: &Connection) {
         let now = now_secs();
-        self.commands = query_top_commands(conn, now, 20);
+        self.commands = query_top_commands(conn, now, 20, self.scope.as_deref());
         self.filtered_commands = self.commands.clone();
         self.list_state.select(None);
     }
 
+    /// Switch between global ranking and ranking for the current directory (or its git root).
+    pub fn toggle_scope(&mut self, conn: &Connection) {
+        self.scope = match self.scope {
+            Some(_) => None,
+            None => current_scope(),
+        };
+        self.load_commands(conn);
+        self.filter_commands(conn);
+    }
+
     /// Re-query commands matching `self.input` via SQL (pushes filter to DB).
     /// Falls back to the pre-loaded top-20 when input is empty.
     pub fn filter_commands(&mut self, conn: &Connection) {
@@ -189,7 +197,8 @@ impl App {
             self.filtered_commands = self.commands.clone();
         } else {
             let now = now_secs();
-            self.filtered_commands = query_filtered_commands(conn, now, &self.input, 50);
+            self.filtered_commands =
+                query_filtered_commands(conn, now, &self.input, 50, self.scope.as_deref());
         }
         self.list_state.select(None);
     }
@@ -231,6 +240,10 @@ impl App {
         self.change_old_command = None;
         self.change_new_alias = String::new();
         self.change_new_alias_cursor_position = 0;
+        self.change_old_text.clear();
+        self.change_new_command.clear();
+        self.change_new_command_cursor_position = 0;
+        self.change_command_editable = true;
         self.change_alias_suggestions.clear();
         self.change_alias_suggestions_state.select(None);
         self.aliases.clear();
@@ -259,7 +272,7 @@ impl App {
             AppMode::RemoveAliasConfirmation => {
                 self.mode = mode;
             }
-            AppMode::ChangeAliasStep1 | AppMode::ChangeAliasStep2 => {
+            AppMode::ChangeAliasStep1 | AppMode::ChangeAliasStep2 | AppMode::ChangeAliasStep3 => {
                 self.mode = mode;
             }
             AppMode::Main => {
@@ -277,9 +290,15 @@ impl App {
     /// Generate alias suggestions, using and populating the session-level cache.
     pub fn generate_alias_suggestions(&mut self) {
         if let Some(command) = self.selected_command.clone() {
+            let wrapper = self
+                .filtered_commands
+                .iter()
+                .chain(&self.selected_command_details)
+                .find(|c| c.command_text == command)
+                .and_then(|c| c.wrapper.clone());
             let paths = self.alias_file_paths.clone();
             let suggester = self.suggester.get_or_insert_with(|| AliasSuggester::new(&paths));
-            self.alias_suggestions = suggester.suggest_aliases(&command);
+            self.alias_suggestions = suggester.suggest_aliases_wrapped(&command, wrapper.as_deref());
         }
     }
 
@@ -296,7 +315,8 @@ impl App {
         crate::registry::list_definitions(conn)
             .unwrap_or_default()
             .iter()
-            .map(|d| (d.name.clone(), renderer.render_template_body(&d.template)))
+            .filter(|d| !d.kind.is_env())
+            .map(|d| (d.name.clone(), renderer.render_summary(d)))
             .collect()
     }
 
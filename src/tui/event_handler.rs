@@ -81,8 +81,9 @@ impl App {
             AppMode::AddAliasConfirmation => self.handle_add_alias_confirmation(key),
             AppMode::RemoveAliasStep1 => self.handle_remove_alias_step1(key),
             AppMode::RemoveAliasConfirmation => self.handle_remove_alias_confirmation(key),
-            AppMode::ChangeAliasStep1 => self.handle_change_alias_step1(key),
+            AppMode::ChangeAliasStep1 => self.handle_change_alias_step1(key, conn),
             AppMode::ChangeAliasStep2 => self.handle_change_alias_step2(key),
+            AppMode::ChangeAliasStep3 => self.handle_change_alias_step3(key),
             AppMode::ListAliases => self.handle_list_aliases(key),
             AppMode::Templates => self.handle_templates(key),
             AppMode::TemplatesNameInput => self.handle_templates_name_input(key, conn),
@@ -117,8 +118,9 @@ impl App {
                 if self.get_selected_template().is_some() {
                     self.template_name_input.clear();
                     self.template_name_cursor_position = 0;
+                    self.template_draft = None;
                     self.mode = AppMode::TemplatesNameInput;
-                    self.status_message = "Enter a name for this template (Enter to save, Esc to cancel):".to_string();
+                    self.status_message = "Enter a name for this template (Enter to continue, Esc to cancel):".to_string();
                 } else {
                     self.status_message = "No template selected.".to_string();
                 }
@@ -136,26 +138,42 @@ impl App {
     fn handle_templates_name_input(&mut self, key: KeyCode, conn: &Connection) -> Option<Operation> {
         match key {
             KeyCode::Enter => {
-                let name = self.template_name_input.trim().to_string();
-                if name.is_empty() {
-                    self.status_message = "Name cannot be empty.".to_string();
-                    return None;
-                }
-                if let Some(mt) = self.get_selected_template() {
+                let input = self.template_name_input.trim().to_string();
+                self.template_name_input.clear();
+                self.template_name_cursor_position = 0;
+
+                let Some((name, mut template)) = self.template_draft.take() else {
+                    // First step: the definition name.
+                    if input.is_empty() {
+                        self.status_message = "Name cannot be empty.".to_string();
+                        return None;
+                    }
+                    let Some(mt) = self.get_selected_template() else {
+                        self.status_message = "No template selected.".to_string();
+                        self.set_mode(AppMode::Main);
+                        return None;
+                    };
                     let template = mt.template.clone();
-                    match crate::ops::apply::apply_add_function(conn, &name, &template) {
-                        Ok(_) => {
-                            self.status_message = format!("Saved template as '{}'.", name);
-                            self.load_commands(conn);
-                            self.set_mode(AppMode::Main);
-                        }
-                        Err(e) => {
-                            self.status_message = format!("Error saving template: {}", e);
-                        }
+                    if template.max_slot() == 0 {
+                        self.save_template(conn, &input, &template);
+                    } else {
+                        self.template_draft = Some((input, template));
+                        self.template_slot = 1;
+                        self.prompt_for_slot();
                     }
+                    return None;
+                };
+
+                // Later steps: one per slot, `name` or `name=default`, blank to skip.
+                if let Some(spec) = crate::template::SlotSpec::parse(&input) {
+                    template.slots.insert(self.template_slot, spec);
+                }
+                if self.template_slot < template.max_slot() {
+                    self.template_draft = Some((name, template));
+                    self.template_slot += 1;
+                    self.prompt_for_slot();
                 } else {
-                    self.status_message = "No template selected.".to_string();
-                    self.set_mode(AppMode::Main);
+                    self.save_template(conn, &name, &template);
                 }
                 None
             }
@@ -174,6 +192,7 @@ impl App {
             KeyCode::Left => { if self.template_name_cursor_position > 0 { self.template_name_cursor_position -= 1; } None }
             KeyCode::Right => { if self.template_name_cursor_position < self.template_name_input.len() { self.template_name_cursor_position += 1; } None }
             KeyCode::Esc => {
+                self.template_draft = None;
                 self.mode = AppMode::Templates;
                 self.status_message = "Cancelled.".to_string();
                 None
@@ -182,6 +201,36 @@ impl App {
         }
     }
 
+    fn prompt_for_slot(&mut self) {
+        if let Some((_, template)) = &self.template_draft {
+            self.status_message = format!(
+                "Name slot {} of {} as name or name=default (Enter to skip):",
+                self.template_slot,
+                template.max_slot()
+            );
+        }
+    }
+
+    fn save_template(&mut self, conn: &Connection, name: &str, template: &crate::template::CommandTemplate) {
+        let slot_values = self
+            .get_selected_template()
+            .map(|mt| mt.slot_values.clone())
+            .unwrap_or_default();
+        match crate::ops::apply::apply_add_function(conn, name, template) {
+            Ok(_) => {
+                for (i, values) in slot_values.iter().enumerate() {
+                    let _ = crate::registry::record_slot_values(conn, name, i as u32 + 1, values);
+                }
+                self.status_message = format!("Saved template as '{}'.", name);
+                self.load_commands(conn);
+                self.set_mode(AppMode::Main);
+            }
+            Err(e) => {
+                self.status_message = format!("Error saving template: {}", e);
+            }
+        }
+    }
+
     fn handle_main_mode(&mut self, key: KeyCode, conn: &Connection) -> Option<Operation> {
         match key {
             KeyCode::Char('q') => {
@@ -249,6 +298,14 @@ impl App {
                 }
                 None
             }
+            KeyCode::Tab => {
+                self.toggle_scope(conn);
+                self.status_message = match &self.scope {
+                    Some(dir) => format!("Ranking commands for {}", dir.display()),
+                    None => "Ranking commands globally".to_string(),
+                };
+                None
+            }
             KeyCode::Char('/') => {
                 self.clear_input();
                 self.status_message = "Type to filter commands... (Esc to clear)".to_string();
@@ -375,7 +432,13 @@ impl App {
                     self.status_message = "Alias name cannot be empty".to_string();
                     return None;
                 }
-                if let Some(command) = &self.selected_command {
+                // A picked wrapper-keeping suggestion carries its own command.
+                let suggested = self
+                    .alias_suggestions
+                    .iter()
+                    .find(|s| s.alias == alias)
+                    .map(|s| s.command.clone());
+                if let Some(command) = suggested.or_else(|| self.selected_command.clone()) {
                     self.confirmation_alias = Some(alias.clone());
                     self.confirmation_command = Some(command.clone());
                     self.confirmation_selection = true;
@@ -450,10 +513,19 @@ impl App {
                             let old_alias_str = old_alias.clone();
                             let new_alias_str = alias.to_string();
                             let command_str = command.to_string();
-                            self.status_message = format!("Changed alias: {} = {} → {} = {}",
-                                old_alias_str, command_str, new_alias_str, command_str);
+                            let rename = (new_alias_str != old_alias_str).then(|| new_alias_str.clone());
+                            let command_changed = self.change_command_editable && command_str != self.change_old_text.trim();
+                            self.status_message = format!("Changed alias: {} → {} = {}",
+                                old_alias_str, new_alias_str, command_str);
                             self.set_mode(AppMode::Main);
-                            Some(Operation::Change { old_alias: old_alias_str, new_alias: new_alias_str })
+                            if command_changed {
+                                Some(Operation::Edit { name: old_alias_str, command: Some(command_str), template: None, rename })
+                            } else if rename.is_some() {
+                                Some(Operation::Change { old_alias: old_alias_str, new_alias: new_alias_str })
+                            } else {
+                                self.status_message = "No changes.".to_string();
+                                None
+                            }
                         } else {
                             let alias_str = alias.to_string();
                             let command_str = command.to_string();
@@ -595,29 +667,36 @@ impl App {
         }
     }
 
-    fn handle_change_alias_step1(&mut self, key: KeyCode) -> Option<Operation> {
+    /// Start changing `alias`: ask for the new name first, then the command.
+    fn begin_change(&mut self, alias: String, command: String, conn: &Connection) {
+        let (text, editable) = match crate::registry::find_definition(conn, &alias) {
+            Ok(Some(def)) if matches!(def.kind, crate::registry::DefinitionKind::Raw(_)) => (command.clone(), false),
+            Ok(Some(def)) => (crate::ops::edit::definition_text(&def), true),
+            _ => (command.clone(), true),
+        };
+        self.change_command_editable = editable;
+        self.change_old_alias = Some(alias);
+        self.change_old_command = Some(command);
+        self.change_new_alias.clear();
+        self.change_new_alias_cursor_position = 0;
+        self.change_new_command_cursor_position = text.len();
+        self.change_new_command = text.clone();
+        self.change_old_text = text;
+        self.set_mode(AppMode::ChangeAliasStep2);
+        self.generate_change_alias_suggestions();
+        self.status_message = "Enter new alias name (empty keeps the current one):".to_string();
+    }
+
+    fn handle_change_alias_step1(&mut self, key: KeyCode, conn: &Connection) -> Option<Operation> {
         match key {
             KeyCode::Enter => {
                 if let Some((alias, command)) = self.get_selected_alias() {
-                    let alias_clone = alias.clone();
-                    let command_clone = command.clone();
-                    self.change_old_alias = Some(alias_clone);
-                    self.change_old_command = Some(command_clone);
-                    self.change_new_alias.clear();
-                    self.change_new_alias_cursor_position = 0;
-                    self.set_mode(AppMode::ChangeAliasStep2);
-                    self.generate_change_alias_suggestions();
-                    self.status_message = "Enter new alias name:".to_string();
+                    let (alias, command) = (alias.clone(), command.clone());
+                    self.begin_change(alias, command, conn);
                 } else if !self.input.trim().is_empty() {
                     let search_alias = self.input.trim().to_string();
-                    if let Some((alias, command)) = self.aliases.iter().find(|(a, _)| a == &search_alias) {
-                        self.change_old_alias = Some(alias.clone());
-                        self.change_old_command = Some(command.clone());
-                        self.change_new_alias.clear();
-                        self.change_new_alias_cursor_position = 0;
-                        self.set_mode(AppMode::ChangeAliasStep2);
-                        self.generate_change_alias_suggestions();
-                        self.status_message = "Enter new alias name:".to_string();
+                    if let Some((alias, command)) = self.aliases.iter().find(|(a, _)| a == &search_alias).cloned() {
+                        self.begin_change(alias, command, conn);
                     } else {
                         self.status_message = "Alias not found".to_string();
                     }
@@ -673,23 +752,21 @@ impl App {
     fn handle_change_alias_step2(&mut self, key: KeyCode) -> Option<Operation> {
         match key {
             KeyCode::Enter => {
-                let new_alias = self.change_new_alias.trim().to_string();
-                if new_alias.is_empty() {
-                    self.status_message = "New alias name cannot be empty".to_string();
+                if self.change_old_alias.is_none() {
+                    self.status_message = "No alias selected for change".to_string();
                     return None;
                 }
-                if let (Some(old_alias), Some(old_command)) = (&self.change_old_alias, &self.change_old_command) {
-                    self.confirmation_alias = Some(new_alias.clone());
-                    self.confirmation_command = Some(old_command.clone());
-                    self.confirmation_selection = true;
-                    self.status_message = format!("Confirm changing alias: {} = {} → {} = {} (OK/Undo)",
-                        old_alias, old_command, new_alias, old_command);
-                    self.set_mode(AppMode::AddAliasConfirmation);
-                    None
-                } else {
-                    self.status_message = "No alias selected for change".to_string();
-                    None
+                if self.change_new_alias.trim().is_empty() {
+                    self.change_new_alias = self.change_old_alias.clone().unwrap_or_default();
                 }
+                self.change_new_alias = self.change_new_alias.trim().to_string();
+                if !self.change_command_editable {
+                    // Raw bodies are changed with `alman add-raw`; only rename here.
+                    return self.handle_change_alias_step3(KeyCode::Enter);
+                }
+                self.set_mode(AppMode::ChangeAliasStep3);
+                self.status_message = "Edit the command (Enter to confirm, Esc to cancel):".to_string();
+                None
             }
             KeyCode::Char(c) => { self.change_new_alias.insert(self.change_new_alias_cursor_position, c); self.change_new_alias_cursor_position += 1; None }
             KeyCode::Backspace => {
@@ -743,6 +820,55 @@ impl App {
         }
     }
 
+    fn handle_change_alias_step3(&mut self, key: KeyCode) -> Option<Operation> {
+        match key {
+            KeyCode::Enter => {
+                let new_command = self.change_new_command.trim().to_string();
+                if new_command.is_empty() {
+                    self.status_message = "Command cannot be empty".to_string();
+                    return None;
+                }
+                let old_alias = self.change_old_alias.clone().unwrap_or_default();
+                let old_command = self.change_old_command.clone().unwrap_or_default();
+                self.confirmation_alias = Some(self.change_new_alias.clone());
+                self.confirmation_command = Some(new_command.clone());
+                self.confirmation_selection = true;
+                self.status_message = format!("Confirm changing alias: {} = {} → {} = {} (OK/Undo)",
+                    old_alias, old_command, self.change_new_alias, new_command);
+                self.set_mode(AppMode::AddAliasConfirmation);
+                None
+            }
+            KeyCode::Char(c) => {
+                self.change_new_command.insert(self.change_new_command_cursor_position, c);
+                self.change_new_command_cursor_position += c.len_utf8();
+                None
+            }
+            KeyCode::Backspace => {
+                if let Some(c) = self.change_new_command[..self.change_new_command_cursor_position].chars().next_back() {
+                    self.change_new_command_cursor_position -= c.len_utf8();
+                    self.change_new_command.remove(self.change_new_command_cursor_position);
+                }
+                None
+            }
+            KeyCode::Left => {
+                if let Some(c) = self.change_new_command[..self.change_new_command_cursor_position].chars().next_back() {
+                    self.change_new_command_cursor_position -= c.len_utf8();
+                }
+                None
+            }
+            KeyCode::Right => {
+                if let Some(c) = self.change_new_command[self.change_new_command_cursor_position..].chars().next() {
+                    self.change_new_command_cursor_position += c.len_utf8();
+                }
+                None
+            }
+            KeyCode::Home => { self.change_new_command_cursor_position = 0; None }
+            KeyCode::End => { self.change_new_command_cursor_position = self.change_new_command.len(); None }
+            KeyCode::Esc => { self.set_mode(AppMode::Main); self.status_message = "Change alias cancelled.".to_string(); None }
+            _ => None,
+        }
+    }
+
     fn handle_list_aliases(&mut self, key: KeyCode) -> Option<Operation> {
         match key {
             KeyCode::Up => {
@@ -1,8 +1,10 @@
 use crate::cli::cli_data::Operation;
 use crate::database::history_loader::compact;
 use crate::database::persistence::ensure_data_directory;
-use crate::ops::apply::{apply_add, apply_change, apply_remove, ApplyOutcome};
+use crate::ops::apply::{apply_add, apply_change, apply_edit, apply_remove, ApplyOutcome};
+use crate::ops::edit::template_from_args;
 use crate::ops::delete_suggestion;
+use crate::ops::render_aliases::print_source_message;
 use crate::tui::app::{App, AppMode};
 use crate::tui::ui::render_ui;
 use ratatui::crossterm::{
@@ -62,17 +64,7 @@ pub fn run_tui(
 
     drop(terminal_guard);
 
-    let shell_path = std::env::var("SHELL").unwrap_or_default();
-    let shell_file = if shell_path.contains("zsh") {
-        "~/.zshrc"
-    } else if shell_path.contains("bash") {
-        "~/.bashrc"
-    } else if shell_path.contains("fish") {
-        "~/.config/fish/config.fish"
-    } else {
-        "your shell's config file"
-    };
-    println!("\nTo use your new aliases immediately, run: \x1b[32msource {}\x1b[0m", shell_file);
+    print_source_message(&conn);
 
     Ok(res?)
 }
@@ -145,6 +137,26 @@ fn handle_operation(operation: Operation, app: &mut App, conn: &Connection) {
                 }
             }
         }
+        Operation::Edit { name, command, template, rename } => {
+            let outcome = template_from_args(command.as_deref(), template.as_deref())
+                .and_then(|t| apply_edit(conn, &name, t.as_ref(), rename.as_deref()));
+            match outcome {
+                Ok(ApplyOutcome::NotFound { .. }) => {
+                    app.status_message = format!("Alias '{}' not found.", name);
+                }
+                Ok(_) => {
+                    let shown = rename.unwrap_or(name);
+                    app.status_message = match command.or(template) {
+                        Some(body) => format!("Edited alias: {} = {}", shown, body),
+                        None => format!("Renamed alias to {}", shown),
+                    };
+                    app.load_commands(conn);
+                }
+                Err(e) => {
+                    app.status_message = format!("Error editing alias: {}", e);
+                }
+            }
+        }
         Operation::List => {
             app.status_message = "List operation handled in TUI mode".to_string();
         }
@@ -161,7 +173,13 @@ fn handle_operation(operation: Operation, app: &mut App, conn: &Connection) {
         Operation::Init { .. } => {
             app.status_message = "Init command not available in TUI mode".to_string();
         }
-        Operation::InitData | Operation::ListAliasFiles => {
+        Operation::InitData
+        | Operation::ListAliasFiles
+        | Operation::Remind { .. }
+        | Operation::AddRaw { .. }
+        | Operation::Env { .. }
+        | Operation::SyncHistory { .. }
+        | Operation::WhichDef { .. } => {
             app.status_message = "Command not available in TUI mode".to_string();
         }
         Operation::RenderAliases { .. }
@@ -16,6 +16,7 @@ pub fn render(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         AppMode::RemoveAliasConfirmation => render_remove_alias_confirmation(f, app, area),
         AppMode::ChangeAliasStep1 => render_change_alias_step1(f, app, area),
         AppMode::ChangeAliasStep2 => render_change_alias_step2(f, app, area),
+        AppMode::ChangeAliasStep3 => render_change_alias_step3(f, app, area),
         AppMode::ListAliases => render_list_aliases(f, app, area),
 
         _ => render_default_input(f, app, area),
@@ -348,7 +349,7 @@ fn render_change_alias_step2(f: &mut Frame, app: &App, area: ratatui::layout::Re
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Enter new alias name (use ↑↓ to navigate suggestions, Tab for first suggestion)"),
+                .title("Enter new alias name, empty to keep it (↑↓ to navigate suggestions, Tab for first suggestion)"),
         );
     f.render_widget(new_alias_input, chunks[1]);
 
@@ -388,6 +389,38 @@ fn render_change_alias_step2(f: &mut Frame, app: &App, area: ratatui::layout::Re
     }
 }
 
+fn render_change_alias_step3(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Selected alias display
+            Constraint::Length(3), // New command input
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    let old_alias = app.change_old_alias.as_deref().unwrap_or("No alias selected");
+    let alias_display = Paragraph::new(format!("{} = {}", old_alias, app.change_old_text))
+        .style(Style::default().fg(Color::Green))
+        .block(Block::default().borders(Borders::ALL).title("Selected Alias"));
+    f.render_widget(alias_display, chunks[0]);
+
+    let command_input = Paragraph::new(app.change_new_command.as_str())
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Command for {} (Enter to confirm, Esc to cancel)", app.change_new_alias)),
+        );
+    f.render_widget(command_input, chunks[1]);
+
+    let cursor = app.change_new_command[..app.change_new_command_cursor_position].chars().count();
+    f.set_cursor_position((
+        chunks[1].x + cursor as u16 + 1,
+        chunks[1].y + 1,
+    ));
+}
+
 fn render_default_input(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -17,7 +17,7 @@ pub fn render(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .filtered_commands
         .iter()
         .map(|cmd| {
-            ListItem::new(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(
                     format!("Score: {:.0} | ", cmd.score),
                     Style::default().fg(Color::Green),
@@ -26,12 +26,23 @@ pub fn render(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
                     &cmd.command_text,
                     Style::default().fg(Color::Blue),
                 ),
-            ]))
+            ];
+            if cmd.local_frequency > 0 {
+                spans.push(Span::styled(
+                    format!("  (here ×{})", cmd.local_frequency),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
+    let title = match &app.scope {
+        Some(dir) => format!("Commands — {}", dir.display()),
+        None => "Commands".to_string(),
+    };
     let commands_list = List::new(commands)
-        .block(Block::default().borders(Borders::ALL).title("Commands"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(Style::default().bg(Color::DarkGray))
         .highlight_symbol(">> ");
 
@@ -54,6 +65,10 @@ pub fn render(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             Span::styled("l", Style::default().fg(Color::Magenta).add_modifier(ratatui::style::Modifier::BOLD)),
             Span::raw(" - List aliases"),
         ]),
+        Line::from(vec![
+            Span::styled("Tab", Style::default().fg(Color::Magenta).add_modifier(ratatui::style::Modifier::BOLD)),
+            Span::raw(" - Here/global"),
+        ]),
         Line::from(vec![
             Span::styled("q", Style::default().fg(Color::Magenta).add_modifier(ratatui::style::Modifier::BOLD)),
             Span::raw(" - Quit"),
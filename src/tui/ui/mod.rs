@@ -73,11 +73,7 @@ fn render_templates(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .mined_templates
         .iter()
         .map(|mt| {
-            let kind = if mt.template.is_zero_slot() || mt.template.only_trailing_single_slot() {
-                DefinitionKind::Alias
-            } else {
-                DefinitionKind::Function
-            };
+            let kind = DefinitionKind::for_template(&mt.template);
             let name = mt
                 .template
                 .parts
@@ -109,10 +105,12 @@ fn render_templates(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .collect();
 
     // Name-input box (only meaningful in TemplatesNameInput mode).
-    let input_title = if matches!(app.mode, AppMode::TemplatesNameInput) {
-        "New name (Enter to save, Esc to cancel)"
-    } else {
-        "Enter to name & save a template"
+    let input_title = match (&app.mode, &app.template_draft) {
+        (AppMode::TemplatesNameInput, Some((name, _))) => {
+            format!("{}: slot {} name[=default] (Enter to continue, Esc to cancel)", name, app.template_slot)
+        }
+        (AppMode::TemplatesNameInput, None) => "New name (Enter to continue, Esc to cancel)".to_string(),
+        _ => "Enter to name & save a template".to_string(),
     };
     let input = Paragraph::new(app.template_name_input.as_str())
         .style(Style::default().fg(Color::White))
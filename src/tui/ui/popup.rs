@@ -29,7 +29,7 @@ pub fn render_command_details_popup(f: &mut Frame, app: &App) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(7), // Details
+                Constraint::Length(8), // Details
                 Constraint::Length(3), // Buttons
             ])
             .split(area);
@@ -47,6 +47,10 @@ pub fn render_command_details_popup(f: &mut Frame, app: &App) {
                 Span::styled("Frequency: ", Style::default().fg(Color::Cyan)),
                 Span::styled(cmd.frequency.to_string(), Style::default().fg(Color::Blue)),
             ]),
+            Line::from(vec![
+                Span::styled("Failures: ", Style::default().fg(Color::Cyan)),
+                Span::styled(format!("{}/{}", cmd.failures, cmd.frequency), Style::default().fg(Color::Red)),
+            ]),
             Line::from(vec![
                 Span::styled("Last Used: ", Style::default().fg(Color::Cyan)),
                 Span::styled(app.format_last_access_time(cmd.last_access_time), Style::default().fg(Color::Magenta)),